@@ -41,4 +41,7 @@ for DIR in ./src/assets/modified_examples/*; do
 done
 printf "${green}done${END}\n"
 
+# Reapply overlays and the other post-build steps the copies just overwrote.
+cargo run -p rustviz-tutorial -- postprocess
+
 mdbook build
\ No newline at end of file
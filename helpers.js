@@ -15,6 +15,12 @@ const SVG = {
     rect: "structBox",
   };
   
+  // the <svg> shown by a panel: an <object> loading the SVG file, or an
+  // <iframe> with the SVG inlined in its srcdoc in the offline bundle
+  function svgRoot(panel) {
+    return panel.contentDocument && panel.contentDocument.querySelector("svg");
+  }
+
   /* --------------------- SIMPLE DRIVER --------------------- */
   function helpers(classname) {
     // console.log("class name is :", classname)
@@ -43,8 +49,8 @@ const SVG = {
     let vis_num = document.getElementsByClassName(classname);
     let code_obj = vis_num[0];
     let tl_obj = vis_num[1];
-    let c_svg = code_obj.contentDocument.firstChild;
-    let tl_svg = tl_obj.contentDocument.firstChild;
+    let c_svg = svgRoot(code_obj);
+    let tl_svg = svgRoot(tl_obj);
     // get elements that will trigger function
     let triggers = tl_svg.getElementsByClassName("fn-trigger");
     var functions = c_svg.getElementsByClassName("fn");
@@ -97,7 +103,7 @@ const SVG = {
           let svg_doc = object.contentDocument;
           let code_width = svg_doc.getElementById("code").getBBox().width;
           let new_width = Math.max(code_width + 30, 400);
-          svg_doc.firstChild.setAttribute("width", new_width + "px");
+          svgRoot(object).setAttribute("width", new_width + "px");
         },
         { once: true }
       );
@@ -106,27 +112,208 @@ const SVG = {
         let svg_doc = object.contentDocument;
         let code_width = svg_doc.getElementById("code").getBBox().width;
         let new_width = Math.max(code_width + 30, 400);
-        svg_doc.firstChild.setAttribute("width", new_width + "px");
+        svgRoot(object).setAttribute("width", new_width + "px");
       }
     }
   }
   
+  /* --------------------- THEME --------------------- */
+
+  // the palette settings in book.toml, which the rustviz preprocessor writes
+  // into every chapter
+  const PALETTE = document.getElementById("rustviz-palette");
+
+  // a dash pattern for each variable's timelines and arrows, when book.toml
+  // asks for cues beyond color; the first variable's stay solid
+  const DASHES = [null, null, "8 3", "2 3", "8 3 2 3", "12 4", "2 6", "8 3 2 3 2 3", "12 3 3 3", "4 4"];
+
+  (function choosePalette() {
+    if (!PALETTE) return;
+    let html = document.documentElement;
+    if (PALETTE.dataset.preset === "colorblind") html.classList.add("rustviz-colorblind");
+    // colors from book.toml replace the preset's in every theme
+    let colors = (PALETTE.dataset.colors || "").split(" ").filter(Boolean);
+    colors.forEach((color, i) => html.style.setProperty("--rustviz-hash-" + (i + 1), color));
+  })();
+
+  // the rules that recolor an SVG with the palette visualization.css sets
+  // for the current mdbook theme
+  function themeStyle() {
+    let palette = getComputedStyle(document.documentElement);
+    let color = (name) => palette.getPropertyValue("--rustviz-" + name).trim();
+    let css =
+      ":root { --bg-color: " + color("bg") + "; --text-color: " + color("code") + "; }\n" +
+      "#heading, #caption { fill: " + color("fg") + "; }\n" +
+      "text.code { fill: var(--text-color); }\n" +
+      '[data-hash="0"] { fill: ' + color("hash-0") + "; }\n" +
+      // triggers focused with the keyboard glow like hovered ones
+      ".tooltip-trigger:focus-visible { filter: url(#glow); }\n" +
+      // the current step of playback, see setUpPlayer
+      "text.code.rustviz-step { font-weight: bold; text-decoration: underline; }\n" +
+      ".tooltip-trigger.rustviz-step { filter: url(#glow); }\n" +
+      "svg.rustviz-playing .tooltip-trigger:not(.rustviz-step) { opacity: 0.35; }\n";
+    for (let i = 1; i < 10; i++) {
+      let hash = color("hash-" + i);
+      css += '[data-hash="' + i + '"] { fill: ' + hash + "; stroke: " + hash + "; }\n";
+    }
+    if (PALETTE && PALETTE.dataset.cues === "true") {
+      // dotted and extended lines, and errors, keep their own dashes
+      for (let i = 2; i < DASHES.length; i++) {
+        css +=
+          ':is(path, line, polyline)[data-hash="' + i + '"]:not(.dotted, .extend, .rustc-error) ' +
+          "{ stroke-dasharray: " + DASHES[i] + "; }\n";
+      }
+    }
+    return css;
+  }
+
+  // add or update the theme stylesheet of one SVG; it comes after the
+  // stylesheets the SVG was generated with, so its rules win
+  function themeSvg(object, css) {
+    let doc = object.contentDocument;
+    let svg = svgRoot(object);
+    if (!svg) return;
+    let style = doc.getElementById("rustviz-theme");
+    if (!style) {
+      style = doc.createElementNS("http://www.w3.org/2000/svg", "style");
+      style.id = "rustviz-theme";
+      svg.appendChild(style);
+    }
+    style.textContent = css;
+  }
+
+  // recolor every visualization on the page; book.js calls this whenever
+  // set_theme runs
+  function applyVisualizationTheme() {
+    let css = themeStyle();
+    for (const object of document.querySelectorAll(".code_panel, .tl_panel")) {
+      themeSvg(object, css);
+    }
+  }
+
+  // SVGs that are still loading are recolored once they have loaded
+  for (const object of document.querySelectorAll(".code_panel, .tl_panel")) {
+    object.addEventListener("load", () => themeSvg(object, themeStyle()));
+  }
+  applyVisualizationTheme();
+
+  // attach the tooltips once the SVGs have loaded, so they can be reached
+  // with the keyboard before the mouse ever enters a panel
+  window.addEventListener("load", function () {
+    for (const object of document.querySelectorAll(".tl_panel")) {
+      if (svgRoot(object)) helpers(object.classList[0]);
+    }
+  });
+
+  /* --------------------- STEP-THROUGH PLAYBACK --------------------- */
+
+  // time each step is shown for while playing
+  const STEP_MS = 3000;
+
+  // wire up the controls the rustviz preprocessor puts under a
+  // visualization; data-steps lists its events in program order
+  function setUpPlayer(player) {
+    let name = player.dataset.example;
+    let steps = JSON.parse(player.dataset.steps);
+    let caption = player.querySelector(".rustviz-caption");
+    let play = player.querySelector(".rustviz-play");
+    let current = -1;
+    let timer = null;
+
+    let panel = (kind) => svgRoot(document.getElementsByClassName(name + " " + kind)[0]);
+
+    function clear() {
+      for (const svg of [panel("code_panel"), panel("tl_panel")]) {
+        for (const elt of svg.querySelectorAll(".rustviz-step")) elt.classList.remove("rustviz-step");
+        svg.classList.remove("rustviz-playing");
+      }
+    }
+
+    function show(i) {
+      clear();
+      current = Math.max(0, Math.min(i, steps.length - 1));
+      let step = steps[current];
+
+      let lines = panel("code_panel").querySelectorAll("#code > text.code:not(.emph)");
+      if (lines[step.line - 1]) lines[step.line - 1].classList.add("rustviz-step");
+
+      let tl_svg = panel("tl_panel");
+      let triggers = tl_svg.getElementsByClassName("tooltip-trigger");
+      for (const j of step.elements) {
+        if (triggers[j]) triggers[j].classList.add("rustviz-step");
+      }
+      tl_svg.classList.add("rustviz-playing");
+
+      caption.textContent =
+        "Step " + (current + 1) + " of " + steps.length + ", line " + step.line + ": " + step.description;
+    }
+
+    function setPlayButton(icon, label) {
+      play.firstChild.className = "fa " + icon;
+      play.title = label;
+      play.setAttribute("aria-label", label);
+    }
+
+    function pause() {
+      clearInterval(timer);
+      timer = null;
+      setPlayButton("fa-play", "Play");
+    }
+
+    function advance() {
+      if (current + 1 >= steps.length) pause();
+      else show(current + 1);
+    }
+
+    play.addEventListener("click", function () {
+      if (timer) return pause();
+      // start over once the last step has been shown
+      if (current + 1 >= steps.length) current = -1;
+      advance();
+      timer = setInterval(advance, STEP_MS);
+      setPlayButton("fa-pause", "Pause");
+    });
+    player.querySelector(".rustviz-next").addEventListener("click", function () {
+      pause();
+      show(current + 1);
+    });
+    player.querySelector(".rustviz-previous").addEventListener("click", function () {
+      pause();
+      show(current - 1);
+    });
+  }
+
+  for (const player of document.querySelectorAll(".rustviz-player")) setUpPlayer(player);
+
   /* --------------------- TOOLTIP-RELATED FUNCTIONS --------------------- */
   
   // change tooltip text on hover
   function displayTooltip(tooltip, classname) {
     // get svg elements
     let tl_obj = document.getElementsByClassName(classname)[1];
-    let tl_svg = tl_obj.contentDocument.firstChild;
+    let tl_svg = svgRoot(tl_obj);
     // get elements that will trigger function
     let triggers = tl_svg.getElementsByClassName("tooltip-trigger");
 
-    let start_line = 0;
-    let end_line = 0;
+    let start_line = null;
+    let end_line = null;
   
     // track time
     var time_start = null;
   
+    // "keyboard" while the tooltip shown was reached by keyboard focus
+    var input = null;
+  
+    // the triggers in source-line order, which Tab and the arrow keys follow;
+    // those not tied to a line, like variable labels, come first
+    let order = Array.from(triggers).sort(function (a, b) {
+      let line = (elt, attr) => parseInt(elt.getAttribute(attr)) || 0;
+      return (
+        line(a, "data-line-start") - line(b, "data-line-start") ||
+        line(a, "data-line-end") - line(b, "data-line-end")
+      );
+    });
+
     for (let i = 0; i < triggers.length; i++) {
       // prevent adding duplicate listeners
       if (triggers[i].classList.contains("listener")) break;
@@ -135,8 +322,24 @@ const SVG = {
       triggers[i].addEventListener("mousemove", showTooltip);
       triggers[i].addEventListener("mouseleave", hideTooltip);
       triggers[i].addEventListener("mouseenter", insertUnderline);
+
+      // a focused trigger shows its tooltip and underline like a hovered one.
+      // the timeline is a single tab stop, held by the trigger focused last,
+      // and Tab moves through the rest in line order before leaving it
+      triggers[i].setAttribute("tabindex", triggers[i] === order[0] ? "0" : "-1");
+      triggers[i].setAttribute(
+        "aria-label",
+        parseText(triggers[i].getAttributeNS(null, "data-tooltip-text"))
+      );
+      triggers[i].addEventListener("focus", focusTooltip);
+      triggers[i].addEventListener("blur", hideTooltip);
     }
-  
+
+    if (!tl_svg.classList.contains("listener")) {
+      tl_svg.classList.add("listener");
+      tl_svg.addEventListener("keydown", moveFocus);
+    }
+
     function showTooltip(e) {
       // console.log("showTooltip")
       // only set time once, prevent from changing every time mouse moves
@@ -154,6 +357,43 @@ const SVG = {
         breakText(text, tooltip);
     }
 
+    // any focus takes the timeline's tab stop, but only keyboard focus shows
+    // the tooltip; a click focuses too, but the mouse is already showing it
+    function focusTooltip(e) {
+      for (const trigger of order) trigger.setAttribute("tabindex", "-1");
+      e.currentTarget.setAttribute("tabindex", "0");
+      if (!e.currentTarget.matches(":focus-visible")) return;
+      input = "keyboard";
+      insertUnderline(e);
+      // place the tooltip as if the mouse were on the middle of the trigger
+      let box = e.currentTarget.getBoundingClientRect();
+      showTooltip({
+        clientX: box.x + box.width / 2,
+        clientY: box.y + box.height / 2,
+        currentTarget: e.currentTarget,
+      });
+    }
+
+    // Tab and the arrow keys step through the triggers in line order; Tab
+    // past either end leaves the timeline as usual
+    function moveFocus(e) {
+      let i = order.indexOf(e.target);
+      if (i === -1) return;
+      let next = {
+        Tab: e.shiftKey ? i - 1 : i + 1,
+        ArrowDown: i + 1,
+        ArrowRight: i + 1,
+        ArrowUp: i - 1,
+        ArrowLeft: i - 1,
+        Home: 0,
+        End: order.length - 1,
+      }[e.key];
+      if (next === undefined) return;
+      if (e.key === "Tab" && (next < 0 || next >= order.length)) return;
+      e.preventDefault();
+      order[Math.max(0, Math.min(next, order.length - 1))].focus();
+    }
+
     // function parseText(text) {
     //   let split_text = text.split(" ");
     //   let words = [];
@@ -186,10 +426,12 @@ const SVG = {
         result = left + " " + right;
       }
 
-      return result;
+      return result.split(/\s+/).join(" ").trim();
     }
   
     function hideTooltip(e) {
+      // nothing is shown, e.g. on blur after the mouse already left
+      if (!time_start) return;
       // console.log("hideTooltip");
       // console.log(e);
       // console.log(e.data-tooltip-text);
@@ -214,38 +456,35 @@ const SVG = {
 
       let hover_time = now - time_start; // time in ms
   
-      var xhr = new XMLHttpRequest();
-      xhr.open("POST", "/action/hover", true); // could add authentification info
-      xhr.setRequestHeader("Content-Type", "application/json");
-      xhr.send(
-        JSON.stringify({
-          svg_name: e.currentTarget.ownerSVGElement.id,
-          hover_item: e_label, 
-          hover_time: hover_time, 
-          start: time_start, 
-          end: now, 
-          hover_message: text, 
-          start_line: parseInt(start_line), 
-          end_line: parseInt(end_line), 
-        })
-      );
-      // console.log(xhr);
+      // sent only with the reader's consent; see telemetry in theme/book.js
+      telemetry.send("hover", {
+        svg_name: e.currentTarget.ownerSVGElement.id,
+        hover_item: e_label, 
+        hover_time: hover_time, 
+        start: time_start, 
+        end: now, 
+        hover_message: text, 
+        start_line: start_line, 
+        end_line: end_line, 
+        input: input || "mouse",
+      });
   
       tooltip.style.display = "none";
       tooltip.innerHTML = "";
 
-      start_line = 0;
-      end_line = 0;
+      start_line = null;
+      end_line = null;
   
       // only track hovering after mouse leaves element
-      gtag("event", "tooltip_hover", {
+      telemetry.track("tooltip_hover", {
         event_label: e_label,
       });
   
-      gtag("event", e_label, {
+      telemetry.track(e_label, {
         hover_time: Date.now() - time_start, // time in ms
       });
       time_start = null; // reset
+      input = null;
   
       removeUnderline(e, classname);
     }
@@ -253,76 +492,35 @@ const SVG = {
     /* ---- SHOW RELEVANT LINES ---- */
     function insertUnderline(e) {
       // console.log("insertUnderline");
+      // start timing here too, in case the mouse leaves without moving
+      if (!time_start) time_start = Date.now();
       let doc = document.getElementsByClassName(classname + " code_panel")[0]
         .contentDocument; //code_panel
-      let begin = 0,
-        end = 0;
-      if (e.currentTarget.tagName === "path") {
-        let arr = e.currentTarget.getAttribute("d").split(" ");
-        if (e.currentTarget.parentNode.id === "ref_line") {
-          begin = parseInt(arr[2]);
-          end = parseInt(begin) + 2 * parseInt(arr[5]) + parseInt(arr[7]) + 5; // + 5 to include last line
-        } else {
-          begin = parseInt(arr[7]);
-          end = parseInt(arr[3]);
-        }
-      } else if (e.currentTarget.tagName === "line") {
-        begin = e.currentTarget.getAttribute("y1");
-        end = e.currentTarget.getAttribute("y2");
-      } else {
-        let pos;
-        if (e.currentTarget.tagName === "circle") {
-          begin = end = parseInt(e.currentTarget.getAttribute("cy")) + 5;
-        } else if (e.currentTarget.tagName === "use") {
-          begin = end = parseInt(e.currentTarget.getAttribute("y")) + 5;
-        } else if (e.currentTarget.tagName === "polyline") {
-          let arr = e.currentTarget.getAttribute("points").split(" ");
-          begin = end = parseInt(arr[1]) + 5;
-        } else {
-          // e.currentTarget.tagName === 'text'
-          begin = end = parseInt(e.currentTarget.getAttribute("y"));
-        }
-      }
-  
-      // add underlining to every relevant line
-      let lines = doc.getElementById("code").children;
-      let len = lines.length; // prevent len from changing
-
-      // start_line and end_line are computed from variables begin and end.
-
-      // begin and end are y-cordinates of the html elements on the website.
-      // They might have a remainder of 25 or 0 when divided by 30 
-      // depending on the graphs generated by the code.
-      // Hence, we handle these situations differently
-      if (parseInt(begin) % 30 !== 0) {
-        start_line = (parseInt(begin) + 5 - 60) / 30;
-      }
-      else {
-        start_line = (parseInt(begin) - 60) / 30;
-      }
 
-      if (parseInt(end) % 30 !== 0) {
-        end_line = (parseInt(end) + 5 - 60) / 30;
-      }
-      else {
-        end_line = (parseInt(end) - 60) / 30;
+      // the lines the element covers, written onto it by
+      // `rustviz-tutorial postprocess`; labels are not tied to a line
+      let tgt = e.currentTarget;
+      if (!tgt.hasAttribute("data-line-start")) {
+        start_line = end_line = null;
+        return;
       }
+      start_line = parseInt(tgt.getAttribute("data-line-start"));
+      end_line = parseInt(tgt.getAttribute("data-line-end"));
 
-      
-      for (let i = 0; i < len; ++i) {
+      // add underlining to every relevant line; line n is the nth line of code
+      let lines = doc.querySelectorAll("#code > text.code:not(.emph)");
+      for (let i = Math.max(start_line, 1) - 1; i < end_line && i < lines.length; ++i) {
         let ly = parseInt(lines[i].getAttribute("y"));
-        if (ly >= begin && ly <= end) {
-
-          // only underline relevant code
-          let emph = doc.createElementNS("http://www.w3.org/2000/svg", "text");
-          emph.setAttribute("class", "code emph");
-          emph.setAttribute("x", "25");
-          emph.setAttribute("y", ly + 3); // +3 to hang just under text
-          emph.innerHTML = new Array(
-            Math.floor(lines[i].getBBox().width / 8) // size of '_' = 8
-          ).join("_"); // string with all underscores
-          doc.getElementById("code").appendChild(emph);
-        }
+
+        // only underline relevant code
+        let emph = doc.createElementNS("http://www.w3.org/2000/svg", "text");
+        emph.setAttribute("class", "code emph");
+        emph.setAttribute("x", "25");
+        emph.setAttribute("y", ly + 3); // +3 to hang just under text
+        emph.innerHTML = new Array(
+          Math.floor(lines[i].getBBox().width / 8) // size of '_' = 8
+        ).join("_"); // string with all underscores
+        doc.getElementById("code").appendChild(emph);
       }
     }
   }
@@ -483,18 +681,12 @@ const SVG = {
     // Your click handler
     time_onpage = new Date() - start;
     end_time = new Date();
-    var xhr = new XMLHttpRequest();
-    xhr.open("POST", "/action/switch", true); // could add authentification info
-    xhr.setRequestHeader("Content-Type", "application/json");
-    xhr.send(
-      JSON.stringify({
-        directory: chapter,
-        time_elpse: time_onpage,
-        start_time: start.getTime(), 
-        end_time: end_time.getTime(), 
-      })
-    );
-    console.log(xhr)
+    telemetry.send("switch", {
+      directory: chapter,
+      time_on_page: time_onpage,
+      start_time: start.getTime(), 
+      end_time: end_time.getTime(), 
+    });
   };
   
   /*window.onload = function () {
@@ -0,0 +1,4 @@
+fn main() {
+    let x = 5;
+    x = 6; // ERROR: cannot assign twice to immutable variable x
+}
@@ -0,0 +1,33 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner mut num;
+Owner mut x;
+MutRef y;
+Owner mut s2;
+Owner n1;
+MutRef s1;
+Function f();
+Function push_str();
+Function println!();
+--- END Variable Definitions --- */
+fn f(s1: &String) {
+    s1.push_str(" 490!");
+}
+
+fn main() {
+    let mut num = 490;
+    let mut x = String::from("EECS");
+    {
+        let y = &mut x;
+        f(y);
+        let mut s2 = x;
+        s2.push_str(" Woo!");
+        println!("{}", s2);
+
+        let n1 = num;
+        println!("{}", n1);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,21 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner mut s;
+Owner some_string;
+Function String::from();
+Function take_and_return_ownership();
+Function println!();
+--- END Variable Definitions --- */
+fn take_and_return_ownership(some_string : String) -> String { // !{ InitOwnerParam(some_string) }
+    println!("{}", some_string); // !{ PassByStaticReference(some_string->println!()) }
+    some_string // !{ Move(some_string->None) }
+} /* !{ GoOutOfScope(some_string) } */
+  
+fn main() {
+    let mut s = String::from("hello"); // !{ Move(String::from()->s) }
+    s = take_and_return_ownership(s); // !{ Move(s->take_and_return_ownership()), Move(take_and_return_ownership()->s) }
+    println!("{}", s);   // OK !{ PassByStaticReference(s->println!()) }
+} /* !{ GoOutOfScope(s) } */
@@ -0,0 +1,24 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner mut s;
+StaticRef r1;
+StaticRef r2;
+MutRef r3;
+Function String::from();
+Function compare_strings();
+Function clear_string();
+--- END Variable Definitions --- */
+fn main(){
+    let mut s = String::from("hello"); // !{ Move(String::from()->s) }
+
+    let r1 = &s; // !{ StaticBorrow(s->r1) }
+    let r2 = &s; // !{ StaticBorrow(s->r2) }
+    assert!(compare_strings(r1, r2)); // !{ PassByStaticReference(r1->compare_strings()), PassByStaticReference(r2->compare_strings()), StaticDie(r1->s), StaticDie(r2->s) }
+
+
+    let r3 = &mut s; // !{ MutableBorrow(s->r3) }
+    clear_string(r3); // !{ PassByMutableReference(r3->clear_string()), MutableDie(r3->s) }
+} /* !{ GoOutOfScope(s), GoOutOfScope(r1), GoOutOfScope(r2), GoOutOfScope(r3) } */
@@ -0,0 +1,19 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner s;
+Owner len1;
+Owner len2;
+Function String::from();
+Function String::len();
+Function len();
+Function println!();
+--- END Variable Definitions --- */
+fn main() {
+    let s = String::from("hello"); // !{ Move(String::from()->s) }
+    let len1 = String::len(&s); // !{ PassByStaticReference(s->String::len()), Move(String::len()->len1) }
+    let len2 = s.len(); // shorthand for the above !{ PassByStaticReference(s->len()), Move(len()->len2) }
+    println!("len1 = {} = len2 = {}", len1, len2); // !{ PassByStaticReference(len1->println!()), PassByStaticReference(len2->println!()) }
+} /* !{ GoOutOfScope(s), GoOutOfScope(len1), GoOutOfScope(len2) } */
@@ -0,0 +1,17 @@
+/* --- BEGIN Variable Definitions ---
+Owner mut x;
+MutRef y;
+Function String::from();
+Function f();
+Function String::push_str();
+--- END Variable Definitions --- */
+fn main() {
+    let mut x = String::from("hello"); // !{ Move(String::from()->x) }
+    let y = &mut x; // !{ MutableBorrow(x->y) }
+    f(&x); // ERROR: y is still live !{ PassByStaticReference(x->f()) }
+    String::push_str(y, ", world"); // !{ PassByMutableReference(y->String::push_str()), MutableDie(y->x) }
+} /* !{ GoOutOfScope(x), GoOutOfScope(y) } */
+
+fn f(x : &String) {
+    println!("{}", x);
+}
@@ -0,0 +1,14 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner x;
+Owner mut y;
+Function String::from();
+--- END Variable Definitions --- */
+fn main() {
+  let x = String::from("hello"); // !{ Move(String::from()->x) }
+  let mut y = String::from("test"); // !{ Move(String::from()->y) }
+  y = x; // !{ Move(x->y) }
+} /* !{ GoOutOfScope(x), GoOutOfScope(y) } */
@@ -0,0 +1,20 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner x;
+Owner y;
+Owner z;
+Function String::from();
+Function println!();
+--- END Variable Definitions --- */
+fn main() {
+    let x = String::from("hello"); // !{ Move(String::from()->x) }
+    let z = {
+        let y = x; // !{ Move(x->y) }
+        println!("{}", y); // !{ PassByStaticReference(y->println!()) }
+        // ...
+    }; // !{ GoOutOfScope(y), Bind(z) }
+    println!("Hello, world!");
+} /* !{ GoOutOfScope(x), GoOutOfScope(z) } */
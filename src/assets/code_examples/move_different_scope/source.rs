@@ -2,7 +2,7 @@ fn main() {
     let x = String::from("hello");
     let z = {
         let y = x;
-        println("{}", y);
+        println!("{}", y);
         // ...
     };
     println!("Hello, world!");
@@ -0,0 +1,21 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner x;
+Owner s;
+Function String::from();
+Function f();
+Function println!();
+--- END Variable Definitions --- */
+fn f() -> String {
+    let x = String::from("hello"); // !{ Move(String::from()->x) }
+    // ...
+    x // !{ Move(x->None) }
+} /* !{ GoOutOfScope(x) } */
+  
+fn main() {
+    let s = f(); // !{ Move(f()->s) }
+    println!("{}", s); // !{ PassByStaticReference(s->println!()) }
+} /* !{ GoOutOfScope(s) } */
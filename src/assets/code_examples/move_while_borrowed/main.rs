@@ -0,0 +1,13 @@
+/* --- BEGIN Variable Definitions ---
+Owner s;
+StaticRef x;
+Owner s2;
+Function String::from();
+Function String::len();
+--- END Variable Definitions --- */
+fn main() {
+    let s = String::from("hello"); // !{ Move(String::from()->s) }
+    let x = &s; // !{ StaticBorrow(s->x) }
+    let s2 = s; // ERROR: cannot move s while a borrow is live !{ Move(s->s2) }
+    println!("{}", String::len(x)); // !{ PassByStaticReference(x->String::len()), StaticDie(x->s) }
+} /* !{ GoOutOfScope(s), GoOutOfScope(x), GoOutOfScope(s2) } */
@@ -0,0 +1,6 @@
+fn main() {
+    let s = String::from("hello");
+    let x = &s;
+    let s2 = s; // ERROR: cannot move s while a borrow is live
+    println!("{}", String::len(x));
+}
@@ -0,0 +1,16 @@
+/* --- BEGIN Variable Definitions ---
+Owner mut x;
+MutRef y;
+MutRef z;
+Function String::from();
+Function String::push_str();
+Function println!();
+--- END Variable Definitions --- */
+fn main() {
+    let mut x = String::from("Hello"); // !{ Move(String::from()->x) }
+    let y = &mut x; // !{ MutableBorrow(x->y) }
+    let z = &mut x; // ERROR: y is still live !{ MutableBorrow(x->z) }
+    String::push_str(y, ", world"); // !{ PassByMutableReference(y->String::push_str()), MutableDie(y->x) }
+    String::push_str(z, ", friend"); // !{ PassByMutableReference(z->String::push_str()), MutableDie(z->x) }
+    println!("{}", x); // !{ PassByStaticReference(x->println!()) }
+} /* !{ GoOutOfScope(x), GoOutOfScope(y), GoOutOfScope(z) } */
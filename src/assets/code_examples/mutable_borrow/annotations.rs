@@ -0,0 +1,21 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner mut x;
+MutRef s;
+Function String::from();
+Function world();
+Function push_str();
+Function println!();
+--- END Variable Definitions --- */
+fn main() {
+    let mut x = String::from("Hello"); // !{ Move(String::from()->x) }
+    world(&mut x); // !{ PassByMutableReference(x->world()) }
+    println!("{}", x); // !{ PassByStaticReference(x->println!()) }
+} /* !{ GoOutOfScope(x) } */
+
+fn world(s : &mut String) { // !{ InitRefParam(s) }
+    s.push_str(", world"); // !{ PassByMutableReference(s->push_str()) }
+} /* !{ GoOutOfScope(s) } */
@@ -0,0 +1,15 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner x;
+Owner y;
+Function String::from();
+Function println!();
+--- END Variable Definitions --- */
+fn main() {
+    let x = String::from("hello"); // !{ Move(String::from()->x) }
+    let y = x; // !{ Move(x->y) }
+    println!("{}", y); // !{ PassByStaticReference(y->println!()) }
+} /* !{ GoOutOfScope(x), GoOutOfScope(y) } */
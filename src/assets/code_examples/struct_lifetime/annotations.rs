@@ -0,0 +1,28 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner n;
+StaticRef first;
+Struct i{p};
+Function String::from();
+--- END Variable Definitions --- */
+struct Excerpt<'a> {
+    p: &'a str,
+}
+
+fn some_function() {
+    let n = String::from("Ok. I'm fine."); // !{ Move(String::from()->n) }
+    let first = n.split('.').next().expect("Could not find a '.'"); // !{ StaticBorrow(n->first) }
+    let i = Excerpt { // !{ Bind(i) }
+        p: first,
+    }; // !{ Copy(first->i.p), StaticDie(first->n) }
+
+    // 'i' cannot be returned be returned
+    // because the struct outlives 'n'
+} /* !{ GoOutOfScope(first), StaticDie(i.p->n), GoOutOfScope(n), GoOutOfScope(i), GoOutOfScope(i.p) } */
+
+fn main() {
+    some_function();
+}
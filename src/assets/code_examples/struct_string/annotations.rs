@@ -0,0 +1,21 @@
+// Not upstream's main.rs: the events of vis_timeline.svg, transcribed so
+// the book knows which source line each row of the timeline stands for.
+// `rustviz-tutorial build` never copies this upstream, and replaces it with
+// upstream's main.rs once it has that. See NOTES.md.
+/* --- BEGIN Variable Definitions ---
+Owner _y;
+Struct f{x, y};
+Function String::from();
+Function println!();
+--- END Variable Definitions --- */
+struct Foo {
+    x: i32,
+    y: String,
+}
+
+fn main() {
+    let _y = String :: from("bar"); // !{ Move(String::from()->_y) }
+    let f = Foo { x: 5, y: _y }; // !{ Bind(f), Bind(f.x), Move(_y->f.y) }
+    println!("{}", f.x); // !{ PassByStaticReference(f.x->println!()) }
+    println!("{}", f.y); // !{ PassByStaticReference(f.y->println!()) }
+} /* !{ GoOutOfScope(f), GoOutOfScope(f.x), GoOutOfScope(f.y) } */
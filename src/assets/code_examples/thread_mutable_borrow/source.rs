@@ -0,0 +1,10 @@
+use std::thread;
+
+fn main() {
+    let mut x = String::from("Hello");
+    let y = &mut x;
+    let z = &mut x; // NOT OK: y is still live
+    thread::spawn(|| { String::push_str(y, ", world"); });
+    String::push_str(z, ", friend");
+    println!("{}", x);
+}
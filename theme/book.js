@@ -3,27 +3,186 @@
 // Fix back button cache problem
 window.onunload = function () {};
 
-(function addAnalytics() {
-    let s = document.createComment(" Global site tag (gtag.js) - Google Analytics for RustViz ");
-    document.head.append(s);
-
-    s = document.createElement('script');
-    s.setAttribute("src", "https://www.googletagmanager.com/gtag/js?id=G-W8JP8LHES3");
-    s.async = true;
-    document.head.append(s);
-
-    s = document.createElement('script');
-    s.innerHTML = "window.dataLayer = window.dataLayer || [];\
-        function gtag(){dataLayer.push(arguments);}\
-        gtag('js', new Date());\
-        gtag('config', 'G-W8JP8LHES3');";
-    document.head.append(s);
-
-    // page viewed
-    gtag('event', 'page_view', {
-        page_path: location.pathname,
-        page_title: document.title
-    });
+// Page views and tooltip events, sent only once the reader agrees in the
+// consent banner. Where they go is chosen at build time by `analytics` in
+// book.toml's [preprocessor.rustviz], which mdbook-rustviz writes into
+// every chapter as #rustviz-telemetry:
+//   "google": Google Analytics, and the /action/* endpoints of the server
+//   "local":  only the /action/* endpoints; no external script is loaded
+//   "off":    nowhere, and no banner is shown
+var telemetry = (function telemetry() {
+    const GA_ID = "G-W8JP8LHES3";
+    const CONSENT_KEY = "rustviz-consent";
+    // the schema version of every event sent; see tools/rustviz-events
+    const SCHEMA_VERSION = 3;
+
+    let marker = document.getElementById("rustviz-telemetry");
+    let mode = marker ? marker.dataset.analytics : "off";
+    let started = false;
+
+    function consent() {
+        try { return localStorage.getItem(CONSENT_KEY); } catch (e) { return null; }
+    }
+
+    function enabled() {
+        return mode !== "off" && consent() === "granted";
+    }
+
+    // random id sent with every event; sessionStorage keeps it across
+    // chapters until the tab is closed
+    function sessionId() {
+        let id = null;
+        try { id = sessionStorage.getItem("rustviz-session"); } catch (e) { }
+        if (!id) {
+            let bytes = crypto.getRandomValues(new Uint8Array(16));
+            id = Array.from(bytes, (b) => b.toString(16).padStart(2, "0")).join("");
+            try { sessionStorage.setItem("rustviz-session", id); } catch (e) { }
+        }
+        return id;
+    }
+
+    // post an event to /action/<name>, stamped with the schema version and
+    // session id; see tools/rustviz-events for the schemas
+    function send(name, fields) {
+        if (!enabled()) {
+            return;
+        }
+        let event = Object.assign({ schema_version: SCHEMA_VERSION, session_id: sessionId() }, fields);
+        let xhr = new XMLHttpRequest();
+        xhr.open("POST", "/action/" + name, true);
+        xhr.setRequestHeader("Content-Type", "application/json");
+        xhr.send(JSON.stringify(event));
+    }
+
+    // a Google Analytics event, dropped unless analytics = "google"
+    function track(name, params) {
+        if (mode === "google" && enabled() && window.gtag) {
+            gtag("event", name, params);
+        }
+    }
+
+    // `rustviz-tutorial bundle` leaves out the lines marked online only, so
+    // the offline copy of the book never reaches for the network
+    function addAnalytics() {
+        // online only {
+        let s = document.createComment(" Global site tag (gtag.js) - Google Analytics for RustViz ");
+        document.head.append(s);
+
+        s = document.createElement('script');
+        s.setAttribute("src", "https://www.googletagmanager.com/gtag/js?id=" + GA_ID);
+        s.async = true;
+        document.head.append(s);
+
+        s = document.createElement('script');
+        s.innerHTML = "window.dataLayer = window.dataLayer || [];\
+            function gtag(){dataLayer.push(arguments);}\
+            gtag('js', new Date());\
+            gtag('config', '" + GA_ID + "');";
+        document.head.append(s);
+        // } online only
+    }
+
+    function start() {
+        if (started) {
+            return;
+        }
+        started = true;
+        window["ga-disable-" + GA_ID] = false;
+        if (mode === "google") {
+            addAnalytics();
+        }
+
+        // page viewed
+        track('page_view', {
+            page_path: location.pathname,
+            page_title: document.title
+        });
+        if (mode === "local") {
+            send("page_view", {
+                page_path: location.pathname,
+                page_title: document.title,
+                time: Date.now(),
+            });
+        }
+    }
+
+    function choose(choice) {
+        try { localStorage.setItem(CONSENT_KEY, choice); } catch (e) { }
+        hideBanner();
+        if (choice === "granted") {
+            start();
+        } else {
+            // stop a gtag.js that is already loaded
+            window["ga-disable-" + GA_ID] = true;
+        }
+    }
+
+    // the banner is a labelled region screen readers can jump to; it only
+    // takes focus when the reader opens it with the settings button
+    function showBanner(focus) {
+        let banner = document.getElementById("rustviz-consent");
+        if (!banner) {
+            banner = document.createElement("div");
+            banner.id = "rustviz-consent";
+            banner.setAttribute("role", "region");
+            banner.setAttribute("aria-label", "Data collection");
+            let where = mode === "google" ? " This uses Google Analytics." : "";
+            banner.innerHTML =
+                "<p>This tutorial is part of a research study (see <a href=\"" + path_to_root +
+                "motivation.html\">Motivation</a>). With your permission, it records which chapters " +
+                "you read and which parts of the visualizations you hover over." + where +
+                " You can change your choice at any time with the <i class=\"fa fa-shield\"></i> " +
+                "button at the top of the page.</p>" +
+                "<button type=\"button\" data-choice=\"granted\">Allow</button> " +
+                "<button type=\"button\" data-choice=\"denied\">Don't allow</button>";
+            banner.querySelectorAll("button").forEach(function (button) {
+                button.addEventListener("click", function () {
+                    choose(button.dataset.choice);
+                });
+            });
+            document.body.append(banner);
+        }
+        banner.hidden = false;
+        if (focus) {
+            banner.querySelector("button").focus();
+        }
+    }
+
+    function hideBanner() {
+        let banner = document.getElementById("rustviz-consent");
+        if (banner) {
+            banner.hidden = true;
+        }
+    }
+
+    function addSettingsButton() {
+        let buttons = document.querySelector(".right-buttons");
+        if (!buttons) {
+            return;
+        }
+        let button = document.createElement("a");
+        button.href = "#";
+        button.id = "rustviz-consent-button";
+        button.title = "Data collection";
+        button.setAttribute("aria-label", "Data collection");
+        button.innerHTML = "<i class=\"fa fa-shield\"></i>";
+        button.addEventListener("click", function (e) {
+            e.preventDefault();
+            showBanner(true);
+        });
+        buttons.prepend(button);
+    }
+
+    if (mode !== "off") {
+        addSettingsButton();
+        if (consent() === null) {
+            showBanner(false);
+        } else if (enabled()) {
+            start();
+        }
+    }
+
+    return { enabled: enabled, send: send, track: track };
 })();
 
 // Global variable, shared between modules
@@ -44,10 +203,10 @@ function adjust_visualization_size(flexbox) {
     let text_width = parseInt(getComputedStyle(document.documentElement).getPropertyValue('--content-max-width'));
     let flex_border_size = parseInt('5px');                              // this parsing in intentional as a hint for the text_width
     
-    let timeline_doc = flexbox.querySelector('object[class*="tl_panel"]').contentDocument.querySelector('svg');
+    let timeline_doc = flexbox.querySelector('[class*="tl_panel"]').contentDocument.querySelector('svg');
     let timeline_width = parseInt(timeline_doc.width.baseVal.value);
     let desired_height = parseInt(timeline_doc.height.baseVal.value);
-    let code_panel_doc = flexbox.querySelector('object[class*="code_panel"]').contentDocument.querySelector('svg');
+    let code_panel_doc = flexbox.querySelector('[class*="code_panel"]').contentDocument.querySelector('svg');
     let code_panel_width = parseInt(code_panel_doc.width.baseVal.value);
 
     // update the div block that surround them with the new width
@@ -79,6 +238,7 @@ function adjust_visualization_size(flexbox) {
     }
 
     var playpens = Array.from(document.querySelectorAll(".playpen"));
+    // online only {
     if (playpens.length > 0) {
         fetch_with_timeout("https://play.rust-lang.org/meta/crates", {
             headers: {
@@ -94,6 +254,7 @@ function adjust_visualization_size(flexbox) {
             playpens.forEach(block => handle_crate_list_update(block, playground_crates));
         });
     }
+    // } online only
 
     function handle_crate_list_update(playpen_block, playground_crates) {
         // update the play buttons after receiving the response
@@ -177,6 +338,7 @@ function adjust_visualization_size(flexbox) {
             params.version = "nightly";
         }
 
+        // online only {
         result_block.innerText = "Running...";
 
         fetch_with_timeout("https://play.rust-lang.org/evaluate.json", {
@@ -190,6 +352,7 @@ function adjust_visualization_size(flexbox) {
         .then(response => response.json())
         .then(response => result_block.innerText = response.result)
         .catch(error => result_block.innerText = "Playground Communication: " + error.message);
+        // } online only
     }
 
     // Syntax highlighting Configuration
@@ -452,6 +615,12 @@ function adjust_visualization_size(flexbox) {
 
         html.classList.remove(previousTheme);
         html.classList.add(theme);
+
+        // helpers.js recolors the visualizations; it loads after this file,
+        // so it is not there yet when the stored theme is first set
+        if (typeof applyVisualizationTheme === "function") {
+            applyVisualizationTheme();
+        }
     }
 
     // Set theme
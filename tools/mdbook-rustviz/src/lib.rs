@@ -0,0 +1,445 @@
+//! mdbook preprocessor for the RustViz tutorial.
+//!
+//! Replaces every `{{#rustviz example_name [modified]}}` directive with the
+//! markup that shows the example's `vis_code.svg` next to its
+//! `vis_timeline.svg` and wires the timeline up to `helpers.js`, followed by
+//! controls that step through the example's events and a collapsed table of
+//! the events for readers who cannot use the tooltips. When the page is
+//! printed, the timeline is replaced by its printable copy with numbered
+//! events, and the tooltips are listed under it as footnotes. The build
+//! fails if the example directory or one of its SVGs is missing.
+//!
+//! `{{#rustviz_output example_name}}` compiles and runs the example and
+//! inserts what it prints. `{{#rustviz_output example_name "text"}}` fails
+//! the build unless the example prints exactly `text`.
+//!
+//! `{{#rustviz_error example_name}}` inserts the errors rustc reports for the
+//! example, and fails the build if it compiles.
+//!
+//! A directive written with a backslash in front, `\{{#rustviz ...}}`, is
+//! shown as it is, without the backslash.
+//!
+//! Every chapter also gets a hidden marker telling `book.js` where to send
+//! telemetry once the reader consents, from `analytics` in
+//! `[preprocessor.rustviz]`; see [`Analytics`]. Another tells `helpers.js`
+//! which colors to draw variables in; see [`Palette`].
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use mdbook::book::{Book, BookItem};
+use mdbook::errors::Error;
+use mdbook::preprocess::{Preprocessor, PreprocessorContext};
+use rustviz_tutorial::annotations::Annotations;
+use rustviz_tutorial::build::SVG_FILES;
+use rustviz_tutorial::callouts::{self, Callout, PRINT_FILE};
+use rustviz_tutorial::directive::{self, Kind};
+use rustviz_tutorial::event_table::{self, Row};
+use rustviz_tutorial::rustc::Runner;
+use rustviz_tutorial::steps::{self, Step};
+use rustviz_tutorial::svg::escape;
+use rustviz_tutorial::Layout;
+
+pub struct RustViz;
+
+impl Preprocessor for RustViz {
+    fn name(&self) -> &str {
+        "rustviz"
+    }
+
+    fn run(&self, ctx: &PreprocessorContext, mut book: Book) -> Result<Book, Error> {
+        let layout = Layout::new(&ctx.root);
+        let analytics = Analytics::from_config(ctx)?;
+        let palette = Palette::from_config(ctx)?;
+        let mut runner = Runner::new()?;
+        let mut result = Ok(());
+        book.for_each_mut(|item| {
+            let BookItem::Chapter(chapter) = item else {
+                return;
+            };
+            if result.is_err() {
+                return;
+            }
+            let Some(path) = &chapter.path else {
+                return;
+            };
+            match expand(&layout, &mut runner, path, &chapter.content) {
+                Ok(content) => chapter.content = content + &analytics.marker() + &palette.marker(),
+                Err(err) => result = Err(err.context(format!("in {}", path.display()))),
+            }
+        });
+        result?;
+        Ok(book)
+    }
+}
+
+/// Where `book.js` sends page views and tooltip events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Analytics {
+    /// Google Analytics, plus the `/action/*` endpoints of whatever serves
+    /// the book. The default.
+    Google,
+    /// Only the `/action/*` endpoints, which `rustviz-server` logs. No
+    /// external script is loaded.
+    Local,
+    /// Nowhere. No consent banner is shown.
+    Off,
+}
+
+impl Analytics {
+    /// Reads `analytics` from `[preprocessor.rustviz]` in book.toml.
+    pub fn from_config(ctx: &PreprocessorContext) -> Result<Self> {
+        let Some(value) = ctx
+            .config
+            .get_preprocessor("rustviz")
+            .and_then(|table| table.get("analytics"))
+        else {
+            return Ok(Analytics::Google);
+        };
+        match value.as_str() {
+            Some("google") => Ok(Analytics::Google),
+            Some("local") => Ok(Analytics::Local),
+            Some("off") => Ok(Analytics::Off),
+            _ => bail!(
+                "preprocessor.rustviz.analytics must be \"google\", \"local\" or \"off\", not {value}"
+            ),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Analytics::Google => "google",
+            Analytics::Local => "local",
+            Analytics::Off => "off",
+        }
+    }
+
+    /// The element `book.js` reads the setting from, appended to a chapter.
+    pub fn marker(self) -> String {
+        format!(
+            "\n\n<div id=\"rustviz-telemetry\" data-analytics=\"{}\" hidden></div>\n",
+            self.name()
+        )
+    }
+}
+
+/// The colors variables are drawn in, set by `palette`, `colors` and `cues`
+/// in `[preprocessor.rustviz]`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Palette {
+    pub preset: Preset,
+    /// Colors for `data-hash` 1 to 9, replacing the preset's in every theme.
+    pub colors: Option<Vec<String>>,
+    /// Whether timelines also draw each variable with its own dash pattern,
+    /// so they can be told apart without color.
+    pub cues: bool,
+}
+
+/// The built-in palettes; `visualization.css` has each for every theme.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Preset {
+    /// The colors the svg_generator uses.
+    #[default]
+    Default,
+    /// Paul Tol's muted and light schemes, which stay distinct with every
+    /// common kind of color blindness.
+    Colorblind,
+}
+
+/// How many `data-hash` colors there are besides 0, the color of code that
+/// is not a variable.
+pub const HASH_COLORS: usize = 9;
+
+impl Palette {
+    pub fn from_config(ctx: &PreprocessorContext) -> Result<Self> {
+        let Some(table) = ctx.config.get_preprocessor("rustviz") else {
+            return Ok(Palette::default());
+        };
+        let preset = match table.get("palette").map(|v| (v, v.as_str())) {
+            None | Some((_, Some("default"))) => Preset::Default,
+            Some((_, Some("colorblind"))) => Preset::Colorblind,
+            Some((value, _)) => bail!(
+                "preprocessor.rustviz.palette must be \"default\" or \"colorblind\", not {value}"
+            ),
+        };
+        let colors = match table.get("colors") {
+            None => None,
+            Some(value) => {
+                let colors: Option<Vec<String>> = value
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(|c| c.as_str().filter(|c| is_hex_color(c)).map(str::to_owned))
+                    .collect();
+                match colors {
+                    Some(colors) if colors.len() == HASH_COLORS => Some(colors),
+                    _ => bail!(
+                        "preprocessor.rustviz.colors must be {HASH_COLORS} colors like \"#1893ff\", not {value}"
+                    ),
+                }
+            }
+        };
+        let cues = match table.get("cues") {
+            None => false,
+            Some(value) => value.as_bool().with_context(|| {
+                format!("preprocessor.rustviz.cues must be true or false, not {value}")
+            })?,
+        };
+        Ok(Palette {
+            preset,
+            colors,
+            cues,
+        })
+    }
+
+    /// The element `helpers.js` reads the palette from, appended to a
+    /// chapter.
+    pub fn marker(&self) -> String {
+        let preset = match self.preset {
+            Preset::Default => "default",
+            Preset::Colorblind => "colorblind",
+        };
+        let colors = self
+            .colors
+            .as_ref()
+            .map(|c| format!(" data-colors=\"{}\"", c.join(" ")))
+            .unwrap_or_default();
+        format!(
+            "\n<div id=\"rustviz-palette\" data-preset=\"{preset}\"{colors} data-cues=\"{}\" hidden></div>\n",
+            self.cues
+        )
+    }
+}
+
+/// `#rgb` or `#rrggbb`.
+fn is_hex_color(color: &str) -> bool {
+    color
+        .strip_prefix('#')
+        .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+/// Expands every directive in a chapter. `chapter` is the chapter's path
+/// relative to the book's `src` directory and decides how the asset paths
+/// are written.
+pub fn expand(
+    layout: &Layout,
+    runner: &mut Runner,
+    chapter: &Path,
+    content: &str,
+) -> Result<String> {
+    let up = "../".repeat(chapter.components().count().saturating_sub(1));
+    let mut out = String::with_capacity(content.len());
+    let mut last = 0;
+    for directive in directive::find(content)? {
+        out.push_str(&directive::unescape(&content[last..directive.range.start]));
+        let rendered = match &directive.kind {
+            Kind::Visualization { modified } => {
+                render_visualization(layout, &up, &directive.name, *modified)?
+            }
+            Kind::Output { expected } => {
+                render_output(layout, runner, &directive.name, expected.as_deref())?
+            }
+            Kind::Error => render_error(layout, runner, &directive.name)?,
+        };
+        out.push_str(&rendered);
+        last = directive.range.end;
+    }
+    out.push_str(&directive::unescape(&content[last..]));
+    Ok(out)
+}
+
+fn render_visualization(layout: &Layout, up: &str, name: &str, modified: bool) -> Result<String> {
+    let (dir, assets) = if modified {
+        (layout.modified_dir(name), "modified_examples")
+    } else {
+        (layout.example_dir(name), "code_examples")
+    };
+    if !dir.is_dir() {
+        bail!("example {name} not found: {} does not exist", dir.display());
+    }
+    for file in SVG_FILES {
+        let svg = dir.join(file);
+        if !svg.is_file() {
+            bail!(
+                "example {name} has no visualization: {} is missing",
+                svg.display()
+            );
+        }
+    }
+    let printed = dir.join(PRINT_FILE);
+    if !printed.is_file() {
+        bail!(
+            "example {name} has no printable timeline: {} is missing; run `rustviz-tutorial postprocess {name}`",
+            printed.display()
+        );
+    }
+    let timeline = dir.join("vis_timeline.svg");
+    let text =
+        fs::read_to_string(&timeline).with_context(|| format!("reading {}", timeline.display()))?;
+    let annotations = Annotations::for_copy(layout, name, &dir)?;
+    let rows = event_table::rows(&text, &annotations)
+        .with_context(|| format!("reading {}", timeline.display()))?;
+    let steps = steps::steps(&text, &annotations)
+        .with_context(|| format!("reading {}", timeline.display()))?;
+    let (_, callouts) = callouts::callouts(&text, &annotations)
+        .with_context(|| format!("reading {}", timeline.display()))?;
+    let base = format!("{up}assets/{assets}/{name}");
+    Ok(format!(
+        r#"<div class="flex-container vis_block" style="position:relative; margin-left:-75px; margin-right:-75px; display: flex;">
+  <object type="image/svg+xml" class="{name} code_panel" data="{base}/vis_code.svg"></object>
+  <object type="image/svg+xml" class="{name} tl_panel" data="{base}/vis_timeline.svg" style="width: auto;" onmouseenter="helpers('{name}')"></object>
+  <img class="rustviz-print" alt="The timeline with its events numbered" src="{base}/{PRINT_FILE}">
+</div>
+{}{}{}"#,
+        render_footnotes(&callouts),
+        render_player(name, &steps)?,
+        render_events(&rows)
+    ))
+}
+
+/// The tooltips of the numbered events, shown only in print.
+fn render_footnotes(callouts: &[Callout]) -> String {
+    let mut html = String::from("<ol class=\"rustviz-print rustviz-footnotes\">\n");
+    for callout in callouts {
+        let variable = callout
+            .variable
+            .as_deref()
+            .map(|v| format!(", <code>{}</code>", escape(v)))
+            .unwrap_or_default();
+        html.push_str(&format!(
+            "<li>{}{variable}: {}</li>\n",
+            describe_lines(callout.lines),
+            escape(&callout.description)
+        ));
+    }
+    html.push_str("</ol>\n");
+    html
+}
+
+fn describe_lines((start, end): (usize, usize)) -> String {
+    if start == end {
+        format!("Line {start}")
+    } else {
+        format!("Lines {start}–{end}")
+    }
+}
+
+/// Buttons that step `helpers.js` through the events, and the caption it
+/// explains each in. Nothing if there are no events.
+fn render_player(name: &str, steps: &[Step]) -> Result<String> {
+    if steps.is_empty() {
+        return Ok(String::new());
+    }
+    let steps = escape(&serde_json::to_string(steps)?);
+    let button = |class: &str, icon: &str, label: &str| {
+        format!(
+            "<button type=\"button\" class=\"{class}\" title=\"{label}\" aria-label=\"{label}\">\
+             <i class=\"fa {icon}\"></i></button>\n"
+        )
+    };
+    Ok(format!(
+        "<div class=\"rustviz-player\" data-example=\"{name}\" data-steps=\"{steps}\">\n\
+         {}{}{}\
+         <span class=\"rustviz-caption\" aria-live=\"polite\"></span>\n\
+         </div>\n",
+        button("rustviz-previous", "fa-step-backward", "Previous step"),
+        button("rustviz-play", "fa-play", "Play"),
+        button("rustviz-next", "fa-step-forward", "Next step"),
+    ))
+}
+
+/// The events as a table in a closed `<details>`. It has no blank lines, so
+/// markdown leaves it alone.
+fn render_events(rows: &[Row]) -> String {
+    let mut html = String::from(
+        "<details class=\"rustviz-events\">\n\
+         <summary>Events in this visualization</summary>\n\
+         <table>\n\
+         <thead><tr><th scope=\"col\">Line</th><th scope=\"col\">Variable</th>\
+         <th scope=\"col\">Event</th><th scope=\"col\">Description</th></tr></thead>\n\
+         <tbody>\n",
+    );
+    for row in rows {
+        let lines = match row.lines {
+            Some((start, end)) if start == end => start.to_string(),
+            Some((start, end)) => format!("{start}–{end}"),
+            None => String::new(),
+        };
+        let variable = row
+            .variable
+            .as_deref()
+            .map(|v| format!("<code>{}</code>", escape(v)))
+            .unwrap_or_default();
+        html.push_str(&format!(
+            "<tr><td>{lines}</td><td>{variable}</td><td>{}</td><td>{}</td></tr>\n",
+            row.kind.description(),
+            escape(&row.description)
+        ));
+    }
+    html.push_str("</tbody>\n</table>\n</details>");
+    html
+}
+
+/// A single line of output becomes inline code; anything longer becomes a
+/// `text` code block, so the directive should then stand on its own line.
+fn render_output(
+    layout: &Layout,
+    runner: &mut Runner,
+    name: &str,
+    expected: Option<&str>,
+) -> Result<String> {
+    let source = example_source(layout, name)?;
+    let stdout = runner
+        .stdout(&source)
+        .with_context(|| format!("running example {name}"))?;
+    let printed = stdout.strip_suffix('\n').unwrap_or(stdout);
+    if let Some(expected) = expected {
+        if expected != printed {
+            bail!("the chapter says example {name} prints {expected:?}, but it prints {printed:?}");
+        }
+    }
+    if printed.is_empty() {
+        bail!("example {name} prints nothing");
+    }
+    if printed.contains('\n') {
+        Ok(format!("```text\n{printed}\n```"))
+    } else {
+        Ok(format!("`{printed}`"))
+    }
+}
+
+/// rustc's errors for an example that is meant not to compile, as a `text`
+/// code block.
+fn render_error(layout: &Layout, runner: &mut Runner, name: &str) -> Result<String> {
+    let source = example_source(layout, name)?;
+    let compilation = runner
+        .compile(&source)
+        .with_context(|| format!("compiling example {name}"))?;
+    if compilation.success() {
+        bail!("example {name} compiles, so it has no errors to show");
+    }
+    let errors: Vec<&str> = compilation
+        .errors()
+        .filter_map(|d| d.rendered.as_deref())
+        .map(str::trim_end)
+        .collect();
+    if errors.is_empty() {
+        bail!(
+            "rustc rejected example {name} without an error:\n{}",
+            compilation.stderr
+        );
+    }
+    Ok(format!("```text\n{}\n```", errors.join("\n\n")))
+}
+
+fn example_source(layout: &Layout, name: &str) -> Result<std::path::PathBuf> {
+    let source = layout.example_dir(name).join("source.rs");
+    if !source.is_file() {
+        bail!(
+            "example {name} not found: {} does not exist",
+            source.display()
+        );
+    }
+    Ok(source)
+}
@@ -0,0 +1,144 @@
+//! Expanding directives in chapters, and reading `[preprocessor.rustviz]`.
+
+use std::path::Path;
+
+use mdbook::preprocess::PreprocessorContext;
+use mdbook::Config;
+use mdbook_rustviz::{expand, Analytics, Palette, Preset};
+use rustviz_tutorial::rustc::Runner;
+use rustviz_tutorial::Layout;
+use serde_json::json;
+
+fn layout() -> Layout {
+    Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap()
+}
+
+/// What mdbook hands the preprocessor for a book.toml with `rustviz`.
+fn context(rustviz: &str) -> PreprocessorContext {
+    let config: Config = format!("[preprocessor.rustviz]\n{rustviz}")
+        .parse()
+        .unwrap();
+    serde_json::from_value(json!({
+        "root": layout().root(),
+        "config": config,
+        "renderer": "html",
+        "mdbook_version": mdbook::MDBOOK_VERSION,
+    }))
+    .unwrap()
+}
+
+#[test]
+fn directives_are_expanded_and_escapes_unescaped() {
+    let mut runner = Runner::new().unwrap();
+    let content = "Run it: {{#rustviz_output printing \"x = 1 and y = 2\"}}\n\
+                   Write \\{{#rustviz move_assignment}} to show it.\n\n\
+                   {{#rustviz move_assignment}}\n";
+    let expanded = expand(
+        &layout(),
+        &mut runner,
+        Path::new("nested/ownership.md"),
+        content,
+    )
+    .unwrap();
+    assert!(expanded.starts_with(
+        "Run it: `x = 1 and y = 2`\n\
+         Write {{#rustviz move_assignment}} to show it.\n\n\
+         <div class=\"flex-container vis_block\""
+    ));
+    assert!(expanded.contains(
+        r#"data="../assets/code_examples/move_assignment/vis_timeline.svg" style="width: auto;" onmouseenter="helpers('move_assignment')""#
+    ));
+    assert!(expanded.contains("<details class=\"rustviz-events\">"));
+}
+
+#[test]
+fn unknown_examples_and_wrong_output_fail() {
+    let layout = layout();
+    let mut runner = Runner::new().unwrap();
+    let mut expand = |content: &str| {
+        expand(&layout, &mut runner, Path::new("ownership.md"), content)
+            .unwrap_err()
+            .to_string()
+    };
+    let examples = layout.code_examples();
+    assert_eq!(
+        expand("{{#rustviz nonexistent}}"),
+        format!(
+            "example nonexistent not found: {} does not exist",
+            examples.join("nonexistent").display()
+        )
+    );
+    assert_eq!(
+        expand("{{#rustviz_output nonexistent}}"),
+        format!(
+            "example nonexistent not found: {} does not exist",
+            examples.join("nonexistent/source.rs").display()
+        )
+    );
+    assert_eq!(
+        expand("{{#rustviz_output printing \"x = 2\"}}"),
+        "the chapter says example printing prints \"x = 2\", but it prints \"x = 1 and y = 2\""
+    );
+    assert_eq!(
+        expand("{{#rustviz_error printing}}"),
+        "example printing compiles, so it has no errors to show"
+    );
+}
+
+#[test]
+fn analytics_is_read_from_the_config() {
+    let analytics = |rustviz: &str| Analytics::from_config(&context(rustviz));
+    assert_eq!(analytics("").unwrap(), Analytics::Google);
+    assert_eq!(
+        analytics("analytics = \"local\"").unwrap(),
+        Analytics::Local
+    );
+    assert_eq!(analytics("analytics = \"off\"").unwrap(), Analytics::Off);
+    assert_eq!(
+        analytics("analytics = \"on\"").unwrap_err().to_string(),
+        "preprocessor.rustviz.analytics must be \"google\", \"local\" or \"off\", not \"on\""
+    );
+    assert_eq!(
+        Analytics::Local.marker(),
+        "\n\n<div id=\"rustviz-telemetry\" data-analytics=\"local\" hidden></div>\n"
+    );
+}
+
+#[test]
+fn the_palette_is_read_from_the_config() {
+    let palette = |rustviz: &str| Palette::from_config(&context(rustviz));
+    assert_eq!(palette("").unwrap(), Palette::default());
+
+    let colors = r##"["#332288", "#cc6677", "#117733", "#ddcc77", "#88ccee", "#882255", "#44aa99", "#999933", "#a49"]"##;
+    let custom = palette(&format!(
+        "palette = \"colorblind\"\ncolors = {colors}\ncues = true"
+    ))
+    .unwrap();
+    assert_eq!(custom.preset, Preset::Colorblind);
+    assert_eq!(custom.colors.as_ref().unwrap()[8], "#a49");
+    assert!(custom.cues);
+    assert!(custom
+        .marker()
+        .contains(r##"data-preset="colorblind" data-colors="#332288 #cc6677"##));
+
+    for (rustviz, error) in [
+        (
+            "palette = \"dark\"",
+            "preprocessor.rustviz.palette must be \"default\" or \"colorblind\", not \"dark\"",
+        ),
+        (
+            "colors = [\"#332288\"]",
+            "preprocessor.rustviz.colors must be 9 colors like \"#1893ff\", not [\"#332288\"]",
+        ),
+        (
+            "colors = [\"red\", \"#cc6677\", \"#117733\", \"#ddcc77\", \"#88ccee\", \"#882255\", \"#44aa99\", \"#999933\", \"#aa4499\"]",
+            "preprocessor.rustviz.colors must be 9 colors like \"#1893ff\", not [\"red\", \"#cc6677\", \"#117733\", \"#ddcc77\", \"#88ccee\", \"#882255\", \"#44aa99\", \"#999933\", \"#aa4499\"]",
+        ),
+        (
+            "cues = \"yes\"",
+            "preprocessor.rustviz.cues must be true or false, not \"yes\"",
+        ),
+    ] {
+        assert_eq!(palette(rustviz).unwrap_err().to_string(), error, "{rustviz}");
+    }
+}
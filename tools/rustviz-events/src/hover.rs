@@ -0,0 +1,98 @@
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, Schema, SchemaObject};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{check_duration, check_span, Event, Kind, Problem};
+
+/// A tooltip in a timeline was hidden: the reader's mouse left its trigger,
+/// or the trigger lost keyboard focus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Hover {
+    pub schema_version: u32,
+    /// Identifies the browser tab the event came from; `helpers.js` keeps
+    /// it in `sessionStorage` until the tab is closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// The `id` of the timeline's `<svg>`, e.g.
+    /// `tl_examples/move_assignment/input/`.
+    pub svg_name: String,
+    /// What kind of element was hovered.
+    #[schemars(schema_with = "hover_item_schema")]
+    pub hover_item: Kind,
+    /// How long the tooltip was shown, in milliseconds.
+    pub hover_time: i64,
+    /// When the mouse entered the element or it was focused, in
+    /// milliseconds since the epoch.
+    pub start: i64,
+    /// When the mouse left the element or it lost focus, in milliseconds
+    /// since the epoch.
+    pub end: i64,
+    /// The tooltip as plain text.
+    pub hover_message: String,
+    /// The first source line the element covers, if `helpers.js` could
+    /// tell.
+    pub start_line: Option<i64>,
+    /// The last source line the element covers.
+    pub end_line: Option<i64>,
+    /// How the element was reached. Older events have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input: Option<Input>,
+}
+
+/// How the reader reached a tooltip trigger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Input {
+    /// The mouse hovered it.
+    Mouse,
+    /// It was focused with Tab or the arrow keys.
+    Keyboard,
+}
+
+impl Event for Hover {
+    const NAME: &'static str = "hover";
+
+    fn upgrade(fields: &mut Map<String, Value>, from: u32) {
+        if from == 0 {
+            if let Some(Value::String(message)) = fields.get_mut("hover_message") {
+                *message = message.split_whitespace().collect::<Vec<_>>().join(" ");
+            }
+            for field in ["start_line", "end_line"] {
+                if let Some(line) = fields.get_mut(field) {
+                    if line.as_i64().is_some_and(|l| l < 1) {
+                        *line = Value::Null;
+                    }
+                }
+            }
+        }
+    }
+
+    fn problems(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        check_duration(&mut problems, "hover_time", self.hover_time);
+        check_span(&mut problems, ("start", self.start), ("end", self.end));
+        for (field, line) in [("start_line", self.start_line), ("end_line", self.end_line)] {
+            if let Some(value) = line.filter(|l| *l < 0) {
+                problems.push(Problem::NegativeLine { field, value });
+            }
+        }
+        if let (Some(start), Some(end)) = (self.start_line, self.end_line) {
+            check_span(&mut problems, ("start_line", start), ("end_line", end));
+        }
+        problems
+    }
+}
+
+/// The list of the names `Kind` serializes as, without a definition of its
+/// own for each variant.
+fn hover_item_schema(_: &mut SchemaGenerator) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        enum_values: Some(Kind::ALL.iter().map(|k| k.name().into()).collect()),
+        ..Default::default()
+    }
+    .into()
+}
@@ -0,0 +1,79 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// What kind of thing a tooltip trigger in a timeline draws. The names are
+/// the `hover_item`s `helpers.js` logs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Kind {
+    /// A variable's name above its column, or a function's `f` logo.
+    #[serde(rename = "label")]
+    Label,
+    /// The state of a variable between two events.
+    #[serde(rename = "timeline_mut")]
+    Timeline,
+    /// The dashed bracket showing an immutable reference cannot mutate.
+    #[serde(rename = "static_ref_line")]
+    StaticRefLine,
+    /// The solid bracket showing a mutable reference can mutate.
+    #[serde(rename = "mut_ref_line")]
+    MutRefLine,
+    /// A dot on a variable's column.
+    #[serde(rename = "event")]
+    Event,
+    /// A function reading or writing through a reference.
+    #[serde(rename = "function_event")]
+    FunctionEvent,
+    /// A move, copy or borrow between two columns.
+    #[serde(rename = "arrow")]
+    Arrow,
+    /// The box around a struct's fields.
+    #[serde(rename = "structBox")]
+    StructBox,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 8] = [
+        Kind::Label,
+        Kind::Timeline,
+        Kind::StaticRefLine,
+        Kind::MutRefLine,
+        Kind::Event,
+        Kind::FunctionEvent,
+        Kind::Arrow,
+        Kind::StructBox,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Label => "label",
+            Kind::Timeline => "timeline_mut",
+            Kind::StaticRefLine => "static_ref_line",
+            Kind::MutRefLine => "mut_ref_line",
+            Kind::Event => "event",
+            Kind::FunctionEvent => "function_event",
+            Kind::Arrow => "arrow",
+            Kind::StructBox => "structBox",
+        }
+    }
+
+    /// What the element is, in words, for readers who cannot see it.
+    pub fn description(self) -> &'static str {
+        match self {
+            Kind::Label => "label",
+            Kind::Timeline => "state",
+            Kind::StaticRefLine => "cannot mutate",
+            Kind::MutRefLine => "can mutate",
+            Kind::Event => "event",
+            Kind::FunctionEvent => "function call",
+            Kind::Arrow => "move, copy or borrow",
+            Kind::StructBox => "struct",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
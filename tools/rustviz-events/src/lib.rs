@@ -0,0 +1,199 @@
+//! The interaction events the tutorial book sends, as typed records.
+//!
+//! `helpers.js` posts a [`Hover`] to `/action/hover` and a [`Switch`] to
+//! `/action/switch`, and `book.js` may post a [`PageView`] to
+//! `/action/page_view`. Every event carries the `schema_version` it was written
+//! with. [`parse`] accepts any version up to [`SCHEMA_VERSION`], upgrades
+//! older ones to the current shape and checks the result:
+//!
+//! ```
+//! use rustviz_events::{parse, Switch};
+//! use serde_json::json;
+//!
+//! // Version 0 events predate `schema_version` and misspell `time_on_page`.
+//! let switch: Switch = parse(json!({
+//!     "directory": "/ownership.html",
+//!     "time_elpse": 60000,
+//!     "start_time": 1700000000000_i64,
+//!     "end_time": 1700000060000_i64,
+//! }))
+//! .unwrap();
+//! assert_eq!(switch.time_on_page, 60000);
+//! ```
+//!
+//! The JSON Schema of each event, for tools not written in Rust, comes from
+//! [`json_schema`].
+//!
+//! # Versions
+//!
+//! 0. The payloads `helpers.js` sent before events were versioned. Upgrading
+//!    renames `time_elpse` to `time_on_page`, collapses the whitespace left
+//!    in `hover_message` where tags were stripped, and drops the
+//!    `start_line` and `end_line` below 1 that were guessed from where the
+//!    element was drawn.
+//! 1. Adds `schema_version`.
+//! 2. Adds the optional `session_id`. Older events have none.
+//! 3. Adds the optional `input` to [`Hover`]: `mouse`, or `keyboard` for a
+//!    tooltip shown by keyboard focus. Older events have none, and those of
+//!    version 2 may be either.
+
+use std::fmt;
+
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+mod hover;
+mod kind;
+mod page_view;
+mod switch;
+
+pub use hover::{Hover, Input};
+pub use kind::Kind;
+pub use page_view::PageView;
+pub use switch::Switch;
+
+/// The version of the events this crate writes.
+pub const SCHEMA_VERSION: u32 = 3;
+
+/// An event type: one of [`Hover`], [`Switch`] and [`PageView`].
+pub trait Event: Serialize + DeserializeOwned + JsonSchema {
+    /// The endpoint's last path segment, e.g. `hover`.
+    const NAME: &'static str;
+
+    /// The first schema version an event of this type was written with.
+    const FIRST_VERSION: u32 = 0;
+
+    /// Turns the fields of an event written with schema version `from` into
+    /// those of version `from + 1`.
+    fn upgrade(fields: &mut Map<String, Value>, from: u32);
+
+    /// What is wrong with an event that has the right shape.
+    fn problems(&self) -> Vec<Problem>;
+}
+
+/// A rule a well-formed event breaks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// A duration in milliseconds is below zero.
+    NegativeDuration { field: &'static str, value: i64 },
+    /// A span starts after it ends.
+    StartAfterEnd {
+        start: &'static str,
+        end: &'static str,
+    },
+    /// A source line number is below zero.
+    NegativeLine { field: &'static str, value: i64 },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::NegativeDuration { field, value } => {
+                write!(f, "`{field}` is negative ({value})")
+            }
+            Problem::StartAfterEnd { start, end } => write!(f, "`{start}` is after `{end}`"),
+            Problem::NegativeLine { field, value } => {
+                write!(f, "`{field}` is negative ({value})")
+            }
+        }
+    }
+}
+
+/// Why an event was rejected.
+#[derive(Debug)]
+pub enum Error {
+    /// The event is not a JSON object.
+    NotAnObject,
+    /// `schema_version` is not a version this crate knows, or predates the
+    /// event type.
+    UnknownVersion(Value),
+    /// A field is missing or has the wrong type, or `hover_item` is not a
+    /// known kind.
+    Malformed(serde_json::Error),
+    /// The event breaks the rules in [`Problem`].
+    Invalid(Vec<Problem>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NotAnObject => f.write_str("expected a JSON object"),
+            Error::UnknownVersion(version) => write!(
+                f,
+                "unknown schema_version {version}; the newest is {SCHEMA_VERSION}"
+            ),
+            Error::Malformed(err) => write!(f, "{err}"),
+            Error::Invalid(problems) => {
+                for (i, problem) in problems.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str("; ")?;
+                    }
+                    write!(f, "{problem}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Malformed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Reads an event of any known schema version, upgraded to the current one.
+pub fn parse<E: Event>(value: Value) -> Result<E, Error> {
+    let Value::Object(mut fields) = value else {
+        return Err(Error::NotAnObject);
+    };
+    let version = match fields.get("schema_version") {
+        None => Some(0),
+        Some(v) => v.as_u64().and_then(|v| u32::try_from(v).ok()),
+    };
+    let version = match version {
+        Some(v) if (E::FIRST_VERSION..=SCHEMA_VERSION).contains(&v) => v,
+        _ => {
+            let found = fields.get("schema_version").cloned();
+            return Err(Error::UnknownVersion(found.unwrap_or(Value::Null)));
+        }
+    };
+    for from in version..SCHEMA_VERSION {
+        E::upgrade(&mut fields, from);
+    }
+    fields.insert("schema_version".to_owned(), SCHEMA_VERSION.into());
+
+    let event: E = serde_json::from_value(Value::Object(fields)).map_err(Error::Malformed)?;
+    let problems = event.problems();
+    if problems.is_empty() {
+        Ok(event)
+    } else {
+        Err(Error::Invalid(problems))
+    }
+}
+
+/// The JSON Schema of the current version of `E`.
+pub fn json_schema<E: Event>() -> Value {
+    serde_json::to_value(schemars::schema_for!(E)).expect("schemas serialize")
+}
+
+fn check_duration(problems: &mut Vec<Problem>, field: &'static str, value: i64) {
+    if value < 0 {
+        problems.push(Problem::NegativeDuration { field, value });
+    }
+}
+
+fn check_span(
+    problems: &mut Vec<Problem>,
+    (start, a): (&'static str, i64),
+    (end, b): (&'static str, i64),
+) {
+    if a > b {
+        problems.push(Problem::StartAfterEnd { start, end });
+    }
+}
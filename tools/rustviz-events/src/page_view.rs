@@ -0,0 +1,34 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{Event, Problem};
+
+/// The reader opened a chapter. `book.js` sends it only when the book is
+/// built with `analytics = "local"`; there are no versions of it before 2.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PageView {
+    pub schema_version: u32,
+    /// Identifies the browser tab the event came from; `book.js` keeps it
+    /// in `sessionStorage` until the tab is closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// The path of the chapter, e.g. `/ownership.html`.
+    pub page_path: String,
+    /// The page's `<title>`.
+    pub page_title: String,
+    /// When the chapter was loaded, in milliseconds since the epoch.
+    pub time: i64,
+}
+
+impl Event for PageView {
+    const NAME: &'static str = "page_view";
+    const FIRST_VERSION: u32 = 2;
+
+    fn upgrade(_fields: &mut Map<String, Value>, _from: u32) {}
+
+    fn problems(&self) -> Vec<Problem> {
+        Vec::new()
+    }
+}
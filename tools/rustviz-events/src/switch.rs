@@ -0,0 +1,47 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{check_duration, check_span, Event, Problem};
+
+/// The reader left a chapter, by following a link or closing the tab.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Switch {
+    pub schema_version: u32,
+    /// Identifies the browser tab the event came from; `helpers.js` keeps
+    /// it in `sessionStorage` until the tab is closed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// The path of the chapter left, e.g. `/ownership.html`.
+    pub directory: String,
+    /// How long the chapter was open, in milliseconds.
+    pub time_on_page: i64,
+    /// When the chapter was loaded, in milliseconds since the epoch.
+    pub start_time: i64,
+    /// When it was left, in milliseconds since the epoch.
+    pub end_time: i64,
+}
+
+impl Event for Switch {
+    const NAME: &'static str = "switch";
+
+    fn upgrade(fields: &mut Map<String, Value>, from: u32) {
+        if from == 0 {
+            if let Some(time) = fields.remove("time_elpse") {
+                fields.insert("time_on_page".to_owned(), time);
+            }
+        }
+    }
+
+    fn problems(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+        check_duration(&mut problems, "time_on_page", self.time_on_page);
+        check_span(
+            &mut problems,
+            ("start_time", self.start_time),
+            ("end_time", self.end_time),
+        );
+        problems
+    }
+}
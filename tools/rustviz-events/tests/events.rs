@@ -0,0 +1,188 @@
+//! Parsing, upgrading and validating events, and their JSON Schemas.
+
+use rustviz_events::{
+    json_schema, parse, Error, Hover, Input, Kind, PageView, Problem, Switch, SCHEMA_VERSION,
+};
+use serde_json::{json, Value};
+
+fn hover() -> Value {
+    json!({
+        "schema_version": 3,
+        "session_id": "3f2a9c1e",
+        "svg_name": "tl_examples/move_assignment/input/",
+        "hover_item": "event",
+        "hover_time": 812,
+        "start": 1_700_000_000_000_i64,
+        "end": 1_700_000_000_812_i64,
+        "hover_message": "x acquires ownership of a resource",
+        "start_line": 2,
+        "end_line": 2,
+        "input": "keyboard",
+    })
+}
+
+fn with(mut event: Value, field: &str, value: Value) -> Value {
+    event[field] = value;
+    event
+}
+
+#[test]
+fn current_events_round_trip() {
+    let event: Hover = parse(hover()).unwrap();
+    assert_eq!(event.hover_item, Kind::Event);
+    assert_eq!(event.input, Some(Input::Keyboard));
+    assert_eq!(serde_json::to_value(&event).unwrap(), hover());
+}
+
+#[test]
+fn page_views_round_trip() {
+    let view = json!({
+        "schema_version": 3,
+        "session_id": "3f2a9c1e",
+        "page_path": "/ownership.html",
+        "page_title": "Ownership - Tutorial",
+        "time": 1_700_000_000_000_i64,
+    });
+    let event: PageView = parse(view.clone()).unwrap();
+    assert_eq!(serde_json::to_value(&event).unwrap(), view);
+    assert!(matches!(
+        parse::<PageView>(with(view, "page_title", json!(null))),
+        Err(Error::Malformed(_))
+    ));
+}
+
+#[test]
+fn version_0_events_are_upgraded() {
+    let mut old = hover();
+    old.as_object_mut().unwrap().remove("schema_version");
+    old.as_object_mut().unwrap().remove("session_id");
+    old.as_object_mut().unwrap().remove("input");
+    old["hover_message"] = json!(" x  acquires ownership of a resource ");
+    old["start_line"] = Value::Null;
+    let event: Hover = parse(old.clone()).unwrap();
+    assert_eq!(event.schema_version, SCHEMA_VERSION);
+    assert_eq!(event.hover_message, "x acquires ownership of a resource");
+    assert_eq!(event.start_line, None);
+    assert_eq!(event.end_line, Some(2));
+    assert_eq!(event.session_id, None);
+    assert_eq!(event.input, None);
+
+    let event: Switch = parse(json!({
+        "directory": "/ownership.html",
+        "time_elpse": 60_000,
+        "start_time": 1_700_000_000_000_i64,
+        "end_time": 1_700_000_060_000_i64,
+    }))
+    .unwrap();
+    assert_eq!(event.time_on_page, 60_000);
+}
+
+#[test]
+fn lines_guessed_from_geometry_are_dropped_from_version_0_events() {
+    let mut old = hover();
+    for field in ["schema_version", "session_id", "input"] {
+        old.as_object_mut().unwrap().remove(field);
+    }
+    old["start_line"] = json!(0);
+    old["end_line"] = json!(-3);
+    let event: Hover = parse(old).unwrap();
+    assert_eq!((event.start_line, event.end_line), (None, None));
+
+    // Later versions never guessed, so a line below 1 is still an error.
+    let current = with(hover(), "start_line", json!(-3));
+    assert!(matches!(
+        parse::<Hover>(current),
+        Err(Error::Invalid(problems)) if problems == [Problem::NegativeLine { field: "start_line", value: -3 }]
+    ));
+}
+
+#[test]
+fn page_views_have_no_versions_before_2() {
+    let view = json!({
+        "page_path": "/ownership.html",
+        "page_title": "Ownership - Tutorial",
+        "time": 1_700_000_000_000_i64,
+    });
+    for version in [None, Some(0), Some(1)] {
+        let mut old = view.clone();
+        if let Some(version) = version {
+            old["schema_version"] = json!(version);
+        }
+        assert!(
+            matches!(parse::<PageView>(old), Err(Error::UnknownVersion(_))),
+            "{version:?}"
+        );
+    }
+    let event: PageView = parse(with(view, "schema_version", json!(2))).unwrap();
+    assert_eq!(event.schema_version, SCHEMA_VERSION);
+    assert_eq!(event.session_id, None);
+}
+
+#[test]
+fn broken_rules_are_reported() {
+    let event = with(
+        with(hover(), "hover_time", json!(-5)),
+        "start",
+        json!(1_700_000_000_900_i64),
+    );
+    let event = with(event, "start_line", json!(3));
+    match parse::<Hover>(event) {
+        Err(Error::Invalid(problems)) => assert_eq!(
+            problems,
+            [
+                Problem::NegativeDuration {
+                    field: "hover_time",
+                    value: -5
+                },
+                Problem::StartAfterEnd {
+                    start: "start",
+                    end: "end"
+                },
+                Problem::StartAfterEnd {
+                    start: "start_line",
+                    end: "end_line"
+                },
+            ]
+        ),
+        other => panic!("{other:?}"),
+    }
+}
+
+#[test]
+fn malformed_events_are_rejected() {
+    let unknown_kind = with(hover(), "hover_item", json!("tooltip"));
+    assert!(matches!(
+        parse::<Hover>(unknown_kind),
+        Err(Error::Malformed(_))
+    ));
+    let missing_start = with(hover(), "start", Value::Null);
+    assert!(matches!(
+        parse::<Hover>(missing_start),
+        Err(Error::Malformed(_))
+    ));
+    let future = with(hover(), "schema_version", json!(SCHEMA_VERSION + 1));
+    assert!(matches!(
+        parse::<Hover>(future),
+        Err(Error::UnknownVersion(_))
+    ));
+    assert!(matches!(
+        parse::<Switch>(json!([])),
+        Err(Error::NotAnObject)
+    ));
+}
+
+#[test]
+fn the_schema_lists_every_hover_item() {
+    let schema = json_schema::<Hover>();
+    let kinds: Vec<Kind> =
+        serde_json::from_value(schema["properties"]["hover_item"]["enum"].clone()).unwrap();
+    assert_eq!(kinds, Kind::ALL);
+    assert_eq!(schema["additionalProperties"], json!(false));
+}
+
+#[test]
+fn the_book_sends_the_current_version() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../theme/book.js");
+    let book_js = std::fs::read_to_string(path).unwrap();
+    assert!(book_js.contains(&format!("const SCHEMA_VERSION = {SCHEMA_VERSION};")));
+}
@@ -0,0 +1,218 @@
+//! Pseudonymous participants, and the private store linking them to people.
+//!
+//! Every browser gets a random id in a cookie the first time it sends an
+//! event, which it only does after the reader consents. Logs never contain
+//! that id: records are keyed by a participant key, an HMAC-SHA256 of the
+//! id under a secret salt. The `session_id` each event carries is replaced
+//! the same way before it is stored. The salt, and the mapping from
+//! participant keys to real identities (e.g. the uniqname a student gives
+//! when consenting), live in a private directory apart from the logs. The
+//! mapping is only written by the `identity` commands, never by the server.
+//! The mapping is encrypted with ChaCha20-Poly1305 under a key that is
+//! never written to disk by this tool, so the logs and anything derived
+//! from them can be handed to researchers as they are.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Name of the cookie holding the browser id.
+pub const COOKIE: &str = "rustviz_id";
+
+/// The environment variable holding the identity store's key, as 64 hex
+/// digits.
+pub const KEY_VAR: &str = "RUSTVIZ_IDENTITY_KEY";
+
+const SALT_FILE: &str = "salt";
+const IDENTITIES_FILE: &str = "identities.enc";
+const NONCE_LEN: usize = 12;
+
+/// A new random browser id.
+pub fn new_browser_id() -> String {
+    hex(&rand::random::<[u8; 16]>())
+}
+
+/// A new random key for the identity store, as hex.
+pub fn new_key() -> String {
+    hex(&rand::random::<[u8; 32]>())
+}
+
+/// Turns browser ids into participant keys.
+#[derive(Clone)]
+pub struct Pseudonymizer {
+    salt: Vec<u8>,
+}
+
+impl Pseudonymizer {
+    /// Reads the salt in the private directory `dir`, creating both if this
+    /// is the first run.
+    pub fn open(dir: &Path) -> Result<Self> {
+        create_private_dir(dir)?;
+        let path = dir.join(SALT_FILE);
+        let salt = match fs::read(&path) {
+            Ok(salt) => salt,
+            Err(err) if err.kind() == ErrorKind::NotFound => {
+                let salt = rand::random::<[u8; 32]>().to_vec();
+                write_private(&path, &salt)?;
+                salt
+            }
+            Err(err) => return Err(err).with_context(|| format!("reading {}", path.display())),
+        };
+        Ok(Pseudonymizer { salt })
+    }
+
+    /// The participant key for a browser id.
+    pub fn participant(&self, browser_id: &str) -> String {
+        self.key(&[browser_id.as_bytes()])
+    }
+
+    /// The key a tab's `session_id` is logged under. The id is sent from the
+    /// browser, so it is keyed like a browser id; the prefix keeps session
+    /// keys apart from participant keys.
+    pub fn session(&self, session_id: &str) -> String {
+        self.key(&[b"session\0", session_id.as_bytes()])
+    }
+
+    fn key(&self, parts: &[&[u8]]) -> String {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.salt).expect("any key length");
+        for part in parts {
+            mac.update(part);
+        }
+        hex(&mac.finalize().into_bytes()[..16])
+    }
+}
+
+/// The encrypted mapping from participant keys to real identities.
+pub struct Identities {
+    path: PathBuf,
+    cipher: ChaCha20Poly1305,
+}
+
+impl Identities {
+    /// Opens the mapping in the private directory `dir` with `key`, 64 hex
+    /// digits as made by [`new_key`].
+    pub fn open(dir: &Path, key: &str) -> Result<Self> {
+        let key = unhex(key.trim())
+            .filter(|k| k.len() == 32)
+            .context("the identity key must be 64 hex digits")?;
+        create_private_dir(dir)?;
+        Ok(Identities {
+            path: dir.join(IDENTITIES_FILE),
+            cipher: ChaCha20Poly1305::new(Key::from_slice(&key)),
+        })
+    }
+
+    /// Opens the mapping with the key in [`KEY_VAR`].
+    pub fn from_env(dir: &Path) -> Result<Self> {
+        let key = std::env::var(KEY_VAR)
+            .with_context(|| format!("{KEY_VAR} is not set; `identity keygen` makes a key"))?;
+        Identities::open(dir, &key)
+    }
+
+    /// Every participant key and the identity linked to it.
+    pub fn load(&self) -> Result<BTreeMap<String, String>> {
+        let data = match fs::read(&self.path) {
+            Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(err) => {
+                return Err(err).with_context(|| format!("reading {}", self.path.display()))
+            }
+        };
+        if data.len() < NONCE_LEN {
+            bail!("{} is truncated", self.path.display());
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LEN);
+        let plain = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                anyhow::anyhow!(
+                    "cannot decrypt {}: wrong key or damaged file",
+                    self.path.display()
+                )
+            })?;
+        serde_json::from_slice(&plain).with_context(|| format!("parsing {}", self.path.display()))
+    }
+
+    fn save(&self, identities: &BTreeMap<String, String>) -> Result<()> {
+        let nonce = rand::random::<[u8; NONCE_LEN]>();
+        let plain = serde_json::to_vec(identities)?;
+        let mut data = nonce.to_vec();
+        data.extend(
+            self.cipher
+                .encrypt(Nonce::from_slice(&nonce), plain.as_slice())
+                .expect("encryption cannot fail"),
+        );
+        write_private(&self.path, &data)
+    }
+
+    /// Links `participant` to `identity`, replacing any earlier link.
+    pub fn link(&self, participant: &str, identity: &str) -> Result<()> {
+        let mut identities = self.load()?;
+        identities.insert(participant.to_owned(), identity.to_owned());
+        self.save(&identities)
+    }
+
+    /// Forgets `participant`, returning the identity it was linked to.
+    pub fn unlink(&self, participant: &str) -> Result<Option<String>> {
+        let mut identities = self.load()?;
+        let identity = identities.remove(participant);
+        if identity.is_some() {
+            self.save(&identities)?;
+        }
+        Ok(identity)
+    }
+
+    /// The participant keys linked to `identity`; one per browser it used.
+    pub fn participants(&self, identity: &str) -> Result<Vec<String>> {
+        Ok(self
+            .load()?
+            .into_iter()
+            .filter(|(_, i)| i == identity)
+            .map(|(p, _)| p)
+            .collect())
+    }
+}
+
+fn create_private_dir(dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(dir, fs::Permissions::from_mode(0o700))
+            .with_context(|| format!("restricting {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+/// Replaces `path` with `data` so that a crash leaves either the old or the
+/// new contents.
+fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    let mut file = File::create(&tmp).with_context(|| format!("creating {}", tmp.display()))?;
+    file.write_all(data)
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    fs::rename(&tmp, path).with_context(|| format!("replacing {}", path.display()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
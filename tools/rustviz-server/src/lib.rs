@@ -0,0 +1,23 @@
+//! Serves the built tutorial book and records the interaction events its
+//! pages send.
+//!
+//! `helpers.js` posts a JSON object to `/action/hover` whenever the reader
+//! moves off a tooltip trigger in a visualization, and to `/action/switch`
+//! when they leave a chapter; books built with `analytics = "local"` also
+//! post to `/action/page_view` when a chapter is opened. Events are only
+//! sent once the reader consents. The server appends each one to a log in its
+//! data directory (see [`store`]) and serves every other `GET` from the
+//! `book/` directory `mdbook build` writes. Events are stored under a
+//! pseudonymous participant key per browser; see [`identity`]. Records are
+//! removed only by [`purge`], when a participant opts out or when they fall
+//! out of the retention window.
+
+pub mod identity;
+pub mod purge;
+pub mod report;
+pub mod server;
+pub mod sessions;
+pub mod store;
+
+pub use server::Server;
+pub use store::{Log, Record, Store};
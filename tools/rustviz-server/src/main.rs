@@ -0,0 +1,301 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Component, Path, PathBuf};
+use std::process::ExitCode;
+
+use anyhow::{bail, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use rustviz_events::{json_schema, Hover, PageView, Switch};
+
+use rustviz_server::identity::{self, Identities};
+use rustviz_server::purge;
+use rustviz_server::report::HoverReport;
+use rustviz_server::server::serve;
+use rustviz_server::sessions::Sessions;
+use rustviz_server::{Log, Store};
+use rustviz_tutorial::{chapters, Layout};
+
+#[derive(Parser)]
+#[command(name = "rustviz-server", about, version)]
+struct Cli {
+    /// Root of the tutorial book (the directory containing book.toml).
+    /// Defaults to the nearest parent of the current directory.
+    #[arg(long, global = true)]
+    root: Option<PathBuf>,
+
+    /// Directory holding the interaction logs. Defaults to `logs` in the
+    /// book root.
+    #[arg(long, global = true)]
+    data: Option<PathBuf>,
+
+    /// Directory holding the salt for participant keys and the encrypted
+    /// identities. Defaults to `private` in the book root; it must not be
+    /// inside the data directory.
+    #[arg(long, global = true)]
+    private: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Cmd,
+}
+
+#[derive(Subcommand)]
+enum Cmd {
+    /// Serve the built book and record the events its pages post.
+    Serve {
+        /// Address to listen on.
+        #[arg(long, default_value = "127.0.0.1:8000")]
+        addr: String,
+
+        /// The built book. Defaults to `book` in the book root.
+        #[arg(long)]
+        book: Option<PathBuf>,
+
+        /// Purge records received more than this many days ago, at startup
+        /// and every hour.
+        #[arg(long, value_name = "DAYS")]
+        retention_days: Option<u64>,
+    },
+    /// Summarize the hover log as `hovers.csv` and `hovers.html`.
+    Report {
+        /// Where to write the report. Defaults to `report` in the data
+        /// directory.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Rebuild each session's path through the chapters as `sessions.json`
+    /// and `sessions.txt`.
+    Sessions {
+        /// Where to write them. Defaults to `report` in the data directory.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Delete every record of a participant who opted out, and drop the
+    /// events they send from now on.
+    OptOut {
+        /// The identity whose participant keys to opt out. Needs the key in
+        /// RUSTVIZ_IDENTITY_KEY; the links are removed too.
+        #[arg(long, required_unless_present = "participant")]
+        identity: Option<String>,
+
+        /// A participant key to opt out. May be repeated.
+        #[arg(long)]
+        participant: Vec<String>,
+    },
+    /// Delete every record received more than DAYS days ago.
+    Expire {
+        #[arg(long)]
+        days: u64,
+    },
+    /// Print the JSON Schema of an event.
+    Schema { event: EventKind },
+    /// Manage the encrypted mapping from participant keys to identities.
+    /// Needs the key in RUSTVIZ_IDENTITY_KEY, except for `keygen`.
+    #[command(subcommand)]
+    Identity(IdentityCmd),
+}
+
+#[derive(Subcommand)]
+enum IdentityCmd {
+    /// Print a new random key for the identity store.
+    Keygen,
+    /// Print every participant key and its identity.
+    List,
+    /// Print the participant keys linked to an identity.
+    Find { identity: String },
+    /// Link a participant key to an identity.
+    Link {
+        participant: String,
+        identity: String,
+    },
+    /// Remove a participant key's link.
+    Unlink { participant: String },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum EventKind {
+    Hover,
+    Switch,
+    PageView,
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    let layout = match cli.root {
+        Some(root) => Layout::new(root),
+        None => Layout::discover(&std::env::current_dir()?)?,
+    };
+    let data = cli.data.unwrap_or_else(|| layout.root().join("logs"));
+    let private = cli.private.unwrap_or_else(|| layout.root().join("private"));
+    if absolute(&private)?.starts_with(absolute(&data)?) {
+        bail!(
+            "{} is inside the data directory {}; keep it apart so the logs can be shared without it",
+            private.display(),
+            data.display()
+        );
+    }
+
+    match cli.command {
+        Cmd::Serve {
+            addr,
+            book,
+            retention_days,
+        } => {
+            let book = book.unwrap_or_else(|| layout.book());
+            serve(&addr, &book, &data, &private, retention_days)
+        }
+        Cmd::Report { out } => {
+            let store = Store::open(&data)?;
+            let (hovers, rejected) = store.events(Log::Hover)?;
+            for message in &rejected {
+                eprintln!("skipping {message}");
+            }
+            let report = HoverReport::new(&hovers);
+
+            let out = out.unwrap_or_else(|| data.join("report"));
+            fs::create_dir_all(&out).with_context(|| format!("creating {}", out.display()))?;
+            let csv = out.join("hovers.csv");
+            report.write_csv(&mut BufWriter::new(File::create(&csv)?))?;
+            let html = out.join("hovers.html");
+            report.write_html(&mut BufWriter::new(File::create(&html)?))?;
+            println!(
+                "{} hovers from {} sessions: {}, {}",
+                report.events,
+                report.sessions,
+                csv.display(),
+                html.display()
+            );
+            Ok(())
+        }
+        Cmd::Sessions { out } => {
+            let store = Store::open(&data)?;
+            let (switches, rejected_switches) = store.participant_events(Log::Switch)?;
+            let (hovers, rejected_hovers) = store.participant_events(Log::Hover)?;
+            for message in rejected_switches.iter().chain(&rejected_hovers) {
+                eprintln!("skipping {message}");
+            }
+            let summary = chapters::summary(&layout.src())?;
+            let sessions = Sessions::new(switches, hovers, &summary);
+
+            let out = out.unwrap_or_else(|| data.join("report"));
+            fs::create_dir_all(&out).with_context(|| format!("creating {}", out.display()))?;
+            let json = out.join("sessions.json");
+            sessions.write_json(&mut BufWriter::new(File::create(&json)?))?;
+            let text = out.join("sessions.txt");
+            sessions.write_text(&mut BufWriter::new(File::create(&text)?))?;
+            println!(
+                "{} sessions: {}, {}",
+                sessions.sessions.len(),
+                json.display(),
+                text.display()
+            );
+            Ok(())
+        }
+        Cmd::OptOut {
+            identity,
+            mut participant,
+        } => {
+            let store = Store::open(&data)?;
+            let identities = match &identity {
+                Some(identity) => {
+                    let identities = Identities::from_env(&private)?;
+                    let linked = identities.participants(identity)?;
+                    if linked.is_empty() {
+                        bail!("no participant is linked to {identity}");
+                    }
+                    participant.extend(linked);
+                    Some(identities)
+                }
+                None => None,
+            };
+            let entry = purge::opt_out(&store, &participant)?;
+            if let Some(identities) = identities {
+                for participant in &participant {
+                    identities.unlink(participant)?;
+                }
+            }
+            println!(
+                "removed {} records of {}; logged to {}",
+                entry.total(),
+                participant.join(", "),
+                store.audit_path().display()
+            );
+            Ok(())
+        }
+        Cmd::Expire { days } => {
+            let store = Store::open(&data)?;
+            match purge::expire(&store, days)? {
+                Some(entry) => println!(
+                    "removed {} records received more than {days} days ago; logged to {}",
+                    entry.total(),
+                    store.audit_path().display()
+                ),
+                None => println!("no records received more than {days} days ago"),
+            }
+            Ok(())
+        }
+        Cmd::Schema { event } => {
+            let schema = match event {
+                EventKind::Hover => json_schema::<Hover>(),
+                EventKind::Switch => json_schema::<Switch>(),
+                EventKind::PageView => json_schema::<PageView>(),
+            };
+            println!("{}", serde_json::to_string_pretty(&schema)?);
+            Ok(())
+        }
+        Cmd::Identity(IdentityCmd::Keygen) => {
+            println!("{}", identity::new_key());
+            Ok(())
+        }
+        Cmd::Identity(command) => {
+            let identities = Identities::from_env(&private)?;
+            match command {
+                IdentityCmd::Keygen => unreachable!("handled above"),
+                IdentityCmd::List => {
+                    for (participant, identity) in identities.load()? {
+                        println!("{participant}  {identity}");
+                    }
+                }
+                IdentityCmd::Find { identity } => {
+                    for participant in identities.participants(&identity)? {
+                        println!("{participant}");
+                    }
+                }
+                IdentityCmd::Link {
+                    participant,
+                    identity,
+                } => identities.link(&participant, &identity)?,
+                IdentityCmd::Unlink { participant } => {
+                    if identities.unlink(&participant)?.is_none() {
+                        bail!("{participant} is not linked to an identity");
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// `path` made absolute, with `.` and `..` resolved without following links,
+/// so that `logs/../private` and `private` compare equal.
+fn absolute(path: &Path) -> Result<PathBuf> {
+    let mut resolved = PathBuf::new();
+    for component in std::path::absolute(path)?.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    Ok(resolved)
+}
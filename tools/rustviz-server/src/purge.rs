@@ -0,0 +1,129 @@
+//! Removing records from the logs: everything from participants who opt
+//! out, and everything older than the retention window.
+//!
+//! Students may opt out of the study until seven days after final grades
+//! are issued. [`opt_out`] removes an opted-out participant's records from
+//! every log, and the server drops the events it receives from them
+//! afterwards, checking [`OptedOut`] under the same lock as the append.
+//! [`expire`] removes the records received before the retention window.
+//! Each purge appends an [`AuditEntry`] to the store's audit log saying
+//! when, why and how many records were removed, but nothing about what they
+//! contained.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::ErrorKind;
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::store::{now_millis, Store};
+
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the purge finished, in milliseconds since the epoch.
+    pub time: u64,
+    #[serde(flatten)]
+    pub reason: Reason,
+    /// How many records were removed from each log, by log name.
+    pub removed: BTreeMap<String, usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum Reason {
+    /// The participants opted out; their later events are not stored.
+    OptOut { participants: Vec<String> },
+    /// The records were received more than `days` days ago, before `before`
+    /// in milliseconds since the epoch.
+    Retention { days: u64, before: u64 },
+}
+
+impl AuditEntry {
+    /// The number of records removed from all logs.
+    pub fn total(&self) -> usize {
+        self.removed.values().sum()
+    }
+}
+
+/// Removes every record of `participants` from the logs and excludes them
+/// from now on. The entry is audited even if there was nothing to remove.
+pub fn opt_out(store: &Store, participants: &[String]) -> Result<AuditEntry> {
+    let excluded: BTreeSet<&str> = participants.iter().map(String::as_str).collect();
+    let entry = store.purge(
+        |record| {
+            record
+                .participant
+                .as_deref()
+                .is_some_and(|p| excluded.contains(p))
+        },
+        |removed| {
+            Some(AuditEntry {
+                time: now_millis(),
+                reason: Reason::OptOut {
+                    participants: excluded.iter().map(|&p| p.to_owned()).collect(),
+                },
+                removed,
+            })
+        },
+    )?;
+    Ok(entry.expect("opt-outs are always audited"))
+}
+
+/// Removes every record received more than `days` days ago. The purge is
+/// audited only if it removed something, so it can run as often as needed.
+pub fn expire(store: &Store, days: u64) -> Result<Option<AuditEntry>> {
+    let before = now_millis().saturating_sub(days.saturating_mul(DAY_MILLIS));
+    store.purge(
+        |record| record.received < before,
+        |removed| {
+            let entry = AuditEntry {
+                time: now_millis(),
+                reason: Reason::Retention { days, before },
+                removed,
+            };
+            (entry.total() > 0).then_some(entry)
+        },
+    )
+}
+
+/// Every participant who has opted out, according to the audit log.
+pub fn opted_out(store: &Store) -> Result<BTreeSet<String>> {
+    Ok(store
+        .audit()?
+        .into_iter()
+        .flat_map(|entry| match entry.reason {
+            Reason::OptOut { participants } => participants,
+            Reason::Retention { .. } => Vec::new(),
+        })
+        .collect())
+}
+
+/// The participants who have opted out, kept between events and read again
+/// only when the audit log has grown.
+#[derive(Debug, Default)]
+pub struct OptedOut {
+    audit_len: u64,
+    participants: BTreeSet<String>,
+}
+
+impl OptedOut {
+    /// Whether `participant` has opted out, according to the audit log as
+    /// it is now.
+    pub fn contains(&mut self, store: &Store, participant: &str) -> Result<bool> {
+        let path = store.audit_path();
+        let len = match fs::metadata(&path) {
+            Ok(metadata) => metadata.len(),
+            Err(err) if err.kind() == ErrorKind::NotFound => 0,
+            Err(err) => return Err(err).with_context(|| format!("reading {}", path.display())),
+        };
+        if len != self.audit_len {
+            self.participants = opted_out(store)?;
+            self.audit_len = len;
+        }
+        Ok(self.participants.contains(participant))
+    }
+}
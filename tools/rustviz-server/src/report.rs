@@ -0,0 +1,244 @@
+//! Which parts of the visualizations readers hover, from the hover log.
+//!
+//! Events are grouped by timeline (`svg_name`), by element kind
+//! (`hover_item`), by both, and by timeline and the source lines the element
+//! covers. Each group gets the number of hovers, the median time the tooltip
+//! was shown and the number of sessions it was seen in. Events logged before
+//! `helpers.js` sent a session id count as hovers but not as sessions.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{self, Write};
+
+use rustviz_events::Hover;
+use rustviz_tutorial::svg::escape;
+
+/// What the events of a [`Row`] have in common.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Grouping {
+    Svg,
+    Item,
+    SvgItem,
+    SvgLines,
+}
+
+impl Grouping {
+    pub const ALL: [Grouping; 4] = [
+        Grouping::Svg,
+        Grouping::Item,
+        Grouping::SvgItem,
+        Grouping::SvgLines,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            Grouping::Svg => "By visualization",
+            Grouping::Item => "By element kind",
+            Grouping::SvgItem => "By visualization and element kind",
+            Grouping::SvgLines => "By visualization and code lines",
+        }
+    }
+}
+
+impl fmt::Display for Grouping {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Grouping::Svg => "svg_name",
+            Grouping::Item => "hover_item",
+            Grouping::SvgItem => "svg_name+hover_item",
+            Grouping::SvgLines => "svg_name+lines",
+        })
+    }
+}
+
+/// One group of hover events. The fields the group is not keyed on are
+/// `None`, as are lines `helpers.js` could not tell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub grouping: Grouping,
+    pub svg_name: Option<String>,
+    pub hover_item: Option<&'static str>,
+    pub start_line: Option<i64>,
+    pub end_line: Option<i64>,
+    pub count: usize,
+    /// In milliseconds.
+    pub median_hover_time: f64,
+    pub sessions: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HoverReport {
+    /// Ordered by grouping, then by key.
+    pub rows: Vec<Row>,
+    pub events: usize,
+    pub sessions: usize,
+}
+
+type Key = (
+    Grouping,
+    Option<String>,
+    Option<&'static str>,
+    Option<i64>,
+    Option<i64>,
+);
+
+#[derive(Default)]
+struct Group<'a> {
+    times: Vec<i64>,
+    sessions: BTreeSet<&'a str>,
+}
+
+impl HoverReport {
+    pub fn new(hovers: &[Hover]) -> Self {
+        let mut groups: BTreeMap<Key, Group> = BTreeMap::new();
+        for hover in hovers {
+            let svg = Some(hover.svg_name.clone());
+            let item = Some(hover.hover_item.name());
+            let lines = (hover.start_line, hover.end_line);
+            for key in [
+                (Grouping::Svg, svg.clone(), None, None, None),
+                (Grouping::Item, None, item, None, None),
+                (Grouping::SvgItem, svg.clone(), item, None, None),
+                (Grouping::SvgLines, svg, None, lines.0, lines.1),
+            ] {
+                let group = groups.entry(key).or_default();
+                group.times.push(hover.hover_time);
+                group.sessions.extend(hover.session_id.as_deref());
+            }
+        }
+
+        let rows = groups
+            .into_iter()
+            .map(
+                |((grouping, svg_name, hover_item, start_line, end_line), mut group)| Row {
+                    grouping,
+                    svg_name,
+                    hover_item,
+                    start_line,
+                    end_line,
+                    count: group.times.len(),
+                    median_hover_time: median(&mut group.times),
+                    sessions: group.sessions.len(),
+                },
+            )
+            .collect();
+        let sessions: BTreeSet<&str> = hovers
+            .iter()
+            .filter_map(|h| h.session_id.as_deref())
+            .collect();
+        HoverReport {
+            rows,
+            events: hovers.len(),
+            sessions: sessions.len(),
+        }
+    }
+
+    fn rows(&self, grouping: Grouping) -> impl Iterator<Item = &Row> {
+        self.rows.iter().filter(move |r| r.grouping == grouping)
+    }
+
+    /// Every row, with an empty field wherever the row is not keyed on it.
+    pub fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "by,svg_name,hover_item,start_line,end_line,count,median_hover_time_ms,sessions"
+        )?;
+        for row in &self.rows {
+            let lines = |line: Option<i64>| match (row.grouping, line) {
+                (Grouping::SvgLines, Some(line)) => line.to_string(),
+                _ => String::new(),
+            };
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{},{}",
+                row.grouping,
+                csv_field(row.svg_name.as_deref().unwrap_or_default()),
+                row.hover_item.unwrap_or_default(),
+                lines(row.start_line),
+                lines(row.end_line),
+                row.count,
+                row.median_hover_time,
+                row.sessions
+            )?;
+        }
+        Ok(())
+    }
+
+    /// A standalone page with one table per grouping.
+    pub fn write_html(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Hover report</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n\
+             <h1>Hover report</h1>\n<p>{} hovers from {} sessions.</p>",
+            self.events, self.sessions
+        )?;
+        for grouping in Grouping::ALL {
+            writeln!(out, "<h2>{}</h2>\n<table>\n<tr>", grouping.title())?;
+            let key_columns: &[&str] = match grouping {
+                Grouping::Svg => &["Visualization"],
+                Grouping::Item => &["Element kind"],
+                Grouping::SvgItem => &["Visualization", "Element kind"],
+                Grouping::SvgLines => &["Visualization", "Lines"],
+            };
+            for column in key_columns {
+                write!(out, "<th>{column}</th>")?;
+            }
+            writeln!(
+                out,
+                "<th>Hovers</th><th>Median time (ms)</th><th>Sessions</th></tr>"
+            )?;
+            for row in self.rows(grouping) {
+                write!(out, "<tr>")?;
+                if let Some(svg_name) = &row.svg_name {
+                    write!(out, "<td>{}</td>", escape(svg_name))?;
+                }
+                if let Some(item) = row.hover_item {
+                    write!(out, "<td>{item}</td>")?;
+                }
+                if grouping == Grouping::SvgLines {
+                    write!(out, "<td>{}</td>", line_range(row.start_line, row.end_line))?;
+                }
+                writeln!(
+                    out,
+                    "<td>{}</td><td>{}</td><td>{}</td></tr>",
+                    row.count, row.median_hover_time, row.sessions
+                )?;
+            }
+            writeln!(out, "</table>")?;
+        }
+        writeln!(out, "</body>\n</html>")
+    }
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.75em; text-align: left; }
+td:nth-last-child(-n+3) { text-align: right; }
+";
+
+fn median(times: &mut [i64]) -> f64 {
+    times.sort_unstable();
+    match times.len() {
+        0 => 0.0,
+        n if n % 2 == 1 => times[n / 2] as f64,
+        n => (times[n / 2 - 1] as f64 + times[n / 2] as f64) / 2.0,
+    }
+}
+
+fn line_range(start: Option<i64>, end: Option<i64>) -> String {
+    match (start, end) {
+        (Some(start), Some(end)) if start == end => start.to_string(),
+        (Some(start), Some(end)) => format!("{start}–{end}"),
+        _ => "unknown".to_owned(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
@@ -0,0 +1,307 @@
+//! The HTTP server: the built book, plus the endpoints `helpers.js` posts
+//! interaction events to.
+
+use std::fs;
+use std::io::{Cursor, Read};
+use std::net::SocketAddr;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+use rustviz_events::{Event, Hover, PageView, Switch};
+use serde_json::Value;
+use tiny_http::{Header, Method, Request, Response, StatusCode};
+
+use crate::identity::{self, Pseudonymizer};
+use crate::purge::{self, OptedOut};
+use crate::store::{Log, Record, Store};
+
+/// Events are a few hundred bytes; anything much bigger is not from the book.
+const MAX_EVENT_BYTES: u64 = 64 * 1024;
+
+/// How long a browser keeps its id: long enough to span a term.
+const COOKIE_MAX_AGE: u32 = 365 * 24 * 60 * 60;
+
+/// How often [`serve`] purges records outside the retention window.
+const EXPIRE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub struct Server {
+    http: tiny_http::Server,
+    book: PathBuf,
+    store: Store,
+    pseudonymizer: Pseudonymizer,
+    opted_out: Mutex<OptedOut>,
+}
+
+impl Server {
+    /// Listens on `addr`, serving the built book in `book` and recording
+    /// events in `store` under participant keys from `pseudonymizer`. Port 0
+    /// picks a free port; see [`Server::addr`].
+    pub fn bind(
+        addr: &str,
+        book: impl Into<PathBuf>,
+        store: Store,
+        pseudonymizer: Pseudonymizer,
+    ) -> Result<Self> {
+        let http =
+            tiny_http::Server::http(addr).map_err(|err| anyhow!("listening on {addr}: {err}"))?;
+        Ok(Server {
+            http,
+            book: book.into(),
+            store,
+            pseudonymizer,
+            opted_out: Mutex::default(),
+        })
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.http
+            .server_addr()
+            .to_ip()
+            .expect("listening on a TCP socket")
+    }
+
+    /// Answers requests until the process is stopped. Every write takes
+    /// the store's file lock, so appends and removals never interleave,
+    /// even with other processes such as `purge`.
+    pub fn run(&self) {
+        for request in self.http.incoming_requests() {
+            self.handle(request);
+        }
+    }
+
+    fn handle(&self, mut request: Request) {
+        let path = request
+            .url()
+            .split(['?', '#'])
+            .next()
+            .unwrap_or("/")
+            .to_owned();
+        let response = match (request.method(), path.as_str()) {
+            (Method::Post, "/action/hover") => self.record::<Hover>(&mut request, Log::Hover),
+            (Method::Post, "/action/switch") => self.record::<Switch>(&mut request, Log::Switch),
+            (Method::Post, "/action/page_view") => {
+                self.record::<PageView>(&mut request, Log::PageView)
+            }
+            (Method::Get | Method::Head, _) => self.file(&path),
+            _ => text(405, "method not allowed"),
+        };
+        let status = response.status_code().0;
+        if status >= 400 && status != 404 {
+            eprintln!("{} {path}: {status}", request.method());
+        }
+        if let Err(err) = request.respond(response) {
+            eprintln!("responding to {path}: {err}");
+        }
+    }
+
+    /// Records the event in the request body under the browser's
+    /// participant key. A browser without an id is given one here: the book
+    /// only posts events once the reader has consented, so no cookie is set
+    /// before that.
+    fn record<E: Event>(&self, request: &mut Request, log: Log) -> Response<Cursor<Vec<u8>>> {
+        let (browser_id, new_browser) = match browser_id(request) {
+            Some(id) => (id, false),
+            None => (identity::new_browser_id(), true),
+        };
+        let participant = self.pseudonymizer.participant(&browser_id);
+        let mut response = self.store_event::<E>(request, log, &participant);
+        if new_browser {
+            let cookie = format!(
+                "{}={browser_id}; Path=/; Max-Age={COOKIE_MAX_AGE}; HttpOnly; SameSite=Strict",
+                identity::COOKIE
+            );
+            response.add_header(Header::from_bytes("Set-Cookie", cookie).expect("valid header"));
+        }
+        response
+    }
+
+    /// Stores the event in the request body in `log`, upgraded to the
+    /// current schema version and with its session id keyed like the
+    /// participant. Events that do not follow the schema are
+    /// rejected; events from participants who opted out are accepted and
+    /// dropped.
+    fn store_event<E: Event>(
+        &self,
+        request: &mut Request,
+        log: Log,
+        participant: &str,
+    ) -> Response<Cursor<Vec<u8>>> {
+        let event = match read_json(request) {
+            Ok(event) => event,
+            Err(response) => return response,
+        };
+        let mut event = match rustviz_events::parse::<E>(event) {
+            Ok(event) => serde_json::to_value(event).expect("events serialize"),
+            Err(err) => return text(400, &format!("invalid {} event: {err}", E::NAME)),
+        };
+        if let Some(Value::String(session)) = event.get_mut("session_id") {
+            *session = self.pseudonymizer.session(session);
+        }
+        let record = Record {
+            participant: Some(participant.to_owned()),
+            ..Record::new(event)
+        };
+        let stored = self.store.append_unless(log, &record, || {
+            let mut opted_out = self.opted_out.lock().expect("not poisoned");
+            opted_out.contains(&self.store, participant)
+        });
+        match stored {
+            Ok(_) => Response::from_data(Vec::new()).with_status_code(204),
+            Err(err) => {
+                eprintln!("error: {err:#}");
+                text(500, "could not store the event")
+            }
+        }
+    }
+
+    /// Serves a file of the built book.
+    fn file(&self, url_path: &str) -> Response<Cursor<Vec<u8>>> {
+        let Some(relative) = decode_path(url_path) else {
+            return text(400, "bad path");
+        };
+        let mut path = self.book.join(relative);
+        if path.is_dir() {
+            path.push("index.html");
+        }
+        match fs::read(&path) {
+            Ok(data) => Response::from_data(data).with_header(content_type(&path)),
+            Err(_) => text(404, "not found"),
+        }
+    }
+}
+
+/// The body of `request` as JSON, or the response rejecting it.
+fn read_json(request: &mut Request) -> Result<Value, Response<Cursor<Vec<u8>>>> {
+    let mut body = Vec::new();
+    let read = request
+        .as_reader()
+        .take(MAX_EVENT_BYTES + 1)
+        .read_to_end(&mut body);
+    if read.is_err() {
+        return Err(text(400, "could not read the request body"));
+    }
+    if body.len() as u64 > MAX_EVENT_BYTES {
+        return Err(text(413, "event too large"));
+    }
+    serde_json::from_slice(&body).map_err(|err| text(400, &format!("invalid JSON: {err}")))
+}
+
+/// The browser id in the request's cookie, if it has one.
+fn browser_id(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .filter(|h| h.field.equiv("Cookie"))
+        .flat_map(|h| h.value.as_str().split(';'))
+        .filter_map(|cookie| cookie.trim().split_once('='))
+        .find(|(name, value)| {
+            *name == identity::COOKIE
+                && value.len() == 32
+                && value.bytes().all(|b| b.is_ascii_hexdigit())
+        })
+        .map(|(_, value)| value.to_owned())
+}
+
+/// The relative file path a URL path names, or `None` if it tries to leave
+/// the book directory.
+fn decode_path(url_path: &str) -> Option<PathBuf> {
+    let decoded = percent_decode(url_path.trim_start_matches('/'))?;
+    let path = PathBuf::from(decoded);
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_)))
+        .then_some(path)
+}
+
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            out.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
+fn content_type(path: &Path) -> Header {
+    let mime = match path.extension().and_then(|e| e.to_str()) {
+        Some("html") => "text/html; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("ico") => "image/x-icon",
+        Some("woff") => "font/woff",
+        Some("woff2") => "font/woff2",
+        Some("ttf") => "font/ttf",
+        Some("txt" | "md") => "text/plain; charset=utf-8",
+        _ => "application/octet-stream",
+    };
+    Header::from_bytes("Content-Type", mime).expect("valid header")
+}
+
+fn text(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(message)
+        .with_status_code(StatusCode(status))
+        .with_header(content_type(Path::new("message.txt")))
+}
+
+/// Opens the store in `data` and serves `book` on `addr` until stopped. The
+/// salt is kept in `private`. With `retention_days`, records older than
+/// that are purged at startup and every hour.
+pub fn serve(
+    addr: &str,
+    book: &Path,
+    data: &Path,
+    private: &Path,
+    retention_days: Option<u64>,
+) -> Result<()> {
+    if !book.join("index.html").is_file() {
+        return Err(anyhow!(
+            "{} has no index.html; run `mdbook build` first",
+            book.display()
+        ));
+    }
+    let store = Store::open(data)?;
+    if let Some(days) = retention_days {
+        expire(&store, days)?;
+        let store = store.clone();
+        thread::spawn(move || loop {
+            thread::sleep(EXPIRE_INTERVAL);
+            if let Err(err) = expire(&store, days) {
+                eprintln!("error: {err:#}");
+            }
+        });
+    }
+    let pseudonymizer = Pseudonymizer::open(private)?;
+    let server = Server::bind(addr, book, store, pseudonymizer).context("starting the server")?;
+    eprintln!(
+        "serving {} at http://{}/, logging to {}",
+        book.display(),
+        server.addr(),
+        data.display()
+    );
+    server.run();
+    Ok(())
+}
+
+fn expire(store: &Store, days: u64) -> Result<()> {
+    if let Some(entry) = purge::expire(store, days)? {
+        eprintln!(
+            "purged {} records received more than {days} days ago",
+            entry.total()
+        );
+    }
+    Ok(())
+}
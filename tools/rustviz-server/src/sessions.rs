@@ -0,0 +1,302 @@
+//! Each reader's path through the book, rebuilt from the switch and hover
+//! logs.
+//!
+//! A session is everything one participant sent, from all their browser
+//! tabs, so a reader who opens a chapter in a second tab still has one
+//! path. Events stored before participants were tracked fall back to their
+//! `session_id`, i.e. one tab. A session's switch events become page
+//! visits, ordered by when the page was opened and placed in the book by
+//! the chapter's position in `SUMMARY.md`. Each hover is attached to the
+//! visit it happened during in the same tab. Hovers outside every visit,
+//! e.g. on a page whose switch event was lost when the tab closed, are kept
+//! separately.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use rustviz_events::{Hover, Switch};
+use rustviz_tutorial::chapters::SummaryEntry;
+use serde::Serialize;
+
+use crate::store::FromParticipant;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Sessions {
+    /// Ordered by the time of their first event.
+    pub sessions: Vec<Session>,
+    /// Events with neither a participant nor a session id, which cannot be
+    /// placed in a session.
+    pub without_session: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Session {
+    /// The participant's key, or the tab's `session_id` for events stored
+    /// before participants were tracked.
+    pub id: String,
+    /// Whether `id` is a participant's key.
+    pub participant: bool,
+    /// The `session_id` of each tab, in the order they were first used.
+    pub tabs: Vec<String>,
+    pub visits: Vec<Visit>,
+    /// Hovers that happened during none of the visits.
+    pub unplaced_hovers: Vec<Hover>,
+}
+
+/// The time a chapter was open.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Visit {
+    /// The `session_id` of the tab the page was open in.
+    pub tab: Option<String>,
+    /// The page's path, as logged.
+    pub directory: String,
+    /// The chapter's title in `SUMMARY.md`, if the page is one of its
+    /// chapters.
+    pub chapter: Option<String>,
+    /// The chapter's position in `SUMMARY.md`, from 1.
+    pub position: Option<usize>,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub time_on_page: i64,
+    /// Ordered by start.
+    pub hovers: Vec<Hover>,
+}
+
+impl Sessions {
+    /// Builds the sessions from events paired with the participant that
+    /// sent them, as [`Store::participant_events`] reads them.
+    ///
+    /// [`Store::participant_events`]: crate::Store::participant_events
+    pub fn new(
+        switches: Vec<FromParticipant<Switch>>,
+        hovers: Vec<FromParticipant<Hover>>,
+        summary: &[SummaryEntry],
+    ) -> Self {
+        let mut without_session = 0;
+        let mut by_id: BTreeMap<(bool, String), Session> = BTreeMap::new();
+        for (participant, switch) in switches {
+            let Some(session) = session(&mut by_id, participant, &switch.session_id) else {
+                without_session += 1;
+                continue;
+            };
+            let position = chapter_index(summary, &switch.directory);
+            session.visits.push(Visit {
+                tab: switch.session_id,
+                chapter: position.map(|i| summary[i].title.clone()),
+                position: position.map(|i| i + 1),
+                directory: switch.directory,
+                start_time: switch.start_time,
+                end_time: switch.end_time,
+                time_on_page: switch.time_on_page,
+                hovers: Vec::new(),
+            });
+        }
+        for session in by_id.values_mut() {
+            session.visits.sort_by_key(|v| (v.start_time, v.end_time));
+        }
+        for (participant, hover) in hovers {
+            let Some(session) = session(&mut by_id, participant, &hover.session_id) else {
+                without_session += 1;
+                continue;
+            };
+            match session.visits.iter_mut().find(|v| {
+                v.tab == hover.session_id && (v.start_time..=v.end_time).contains(&hover.start)
+            }) {
+                Some(visit) => visit.hovers.push(hover),
+                None => session.unplaced_hovers.push(hover),
+            }
+        }
+
+        let mut sessions: Vec<Session> = by_id.into_values().collect();
+        for session in &mut sessions {
+            for visit in &mut session.visits {
+                visit.hovers.sort_by_key(|h| h.start);
+            }
+            session.unplaced_hovers.sort_by_key(|h| h.start);
+            let visits = session.visits.iter().map(|v| &v.tab);
+            let hovers = session.unplaced_hovers.iter().map(|h| &h.session_id);
+            for tab in visits.chain(hovers).flatten() {
+                if !session.tabs.contains(tab) {
+                    session.tabs.push(tab.clone());
+                }
+            }
+        }
+        sessions.sort_by_key(|s| (s.start(), s.id.clone()));
+        Sessions {
+            sessions,
+            without_session,
+        }
+    }
+
+    pub fn write_json(&self, out: &mut impl Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, self)?;
+        writeln!(out)
+    }
+
+    /// Each session as an indented timeline, with times relative to its
+    /// first event.
+    pub fn write_text(&self, out: &mut impl Write) -> io::Result<()> {
+        for (i, session) in self.sessions.iter().enumerate() {
+            if i > 0 {
+                writeln!(out)?;
+            }
+            let start = session.start().unwrap_or_default();
+            let who = if session.participant {
+                "participant"
+            } else {
+                "tab"
+            };
+            let mut pages = plural(session.visits.len(), "page");
+            if session.tabs.len() > 1 {
+                pages = format!("{pages} in {} tabs", session.tabs.len());
+            }
+            writeln!(
+                out,
+                "{who} {} (started {}, {pages})",
+                session.id,
+                utc(start)
+            )?;
+            for visit in &session.visits {
+                let place = match (&visit.chapter, visit.position) {
+                    (Some(chapter), Some(position)) => format!("{position}. {chapter}"),
+                    _ => visit.directory.clone(),
+                };
+                writeln!(
+                    out,
+                    "  {:>7}  {place}, {}",
+                    offset(visit.start_time - start),
+                    duration(visit.time_on_page)
+                )?;
+                for hover in &visit.hovers {
+                    writeln!(
+                        out,
+                        "  {:>7}    {}",
+                        offset(hover.start - start),
+                        describe(hover)
+                    )?;
+                }
+            }
+            if !session.unplaced_hovers.is_empty() {
+                writeln!(out, "  outside any page visit:")?;
+                for hover in &session.unplaced_hovers {
+                    writeln!(
+                        out,
+                        "  {:>7}    {}",
+                        offset(hover.start - start),
+                        describe(hover)
+                    )?;
+                }
+            }
+        }
+        if self.without_session > 0 {
+            if !self.sessions.is_empty() {
+                writeln!(out)?;
+            }
+            writeln!(
+                out,
+                "{} without a participant or session id not shown",
+                plural(self.without_session, "event")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Session {
+    /// When the first page was opened or the first hover started.
+    pub fn start(&self) -> Option<i64> {
+        let visits = self.visits.iter().map(|v| v.start_time);
+        let hovers = self.unplaced_hovers.iter().map(|h| h.start);
+        visits.chain(hovers).min()
+    }
+}
+
+/// The session of the participant, or of the tab if there is none.
+fn session<'a>(
+    by_id: &'a mut BTreeMap<(bool, String), Session>,
+    participant: Option<String>,
+    tab: &Option<String>,
+) -> Option<&'a mut Session> {
+    let key = match participant {
+        Some(participant) => (true, participant),
+        None => (false, tab.clone()?),
+    };
+    Some(by_id.entry(key.clone()).or_insert_with(|| Session {
+        id: key.1,
+        participant: key.0,
+        tabs: Vec::new(),
+        visits: Vec::new(),
+        unplaced_hovers: Vec::new(),
+    }))
+}
+
+/// Where the page at `directory` (a URL path) is in `summary`. mdbook's
+/// `index.html` is a copy of the first chapter.
+fn chapter_index(summary: &[SummaryEntry], directory: &str) -> Option<usize> {
+    let page = directory.rsplit('/').next().unwrap_or_default();
+    if page.is_empty() || page == "index.html" {
+        return (!summary.is_empty()).then_some(0);
+    }
+    let file = format!("{}.md", page.strip_suffix(".html")?);
+    summary.iter().position(|entry| entry.file == file)
+}
+
+fn describe(hover: &Hover) -> String {
+    let lines = match (hover.start_line, hover.end_line) {
+        (Some(start), Some(end)) if start == end => format!(" line {start}"),
+        (Some(start), Some(end)) => format!(" lines {start}-{end}"),
+        _ => String::new(),
+    };
+    format!(
+        "{} in {}{lines}, {}: {}",
+        hover.hover_item,
+        hover.svg_name,
+        duration(hover.hover_time),
+        hover.hover_message
+    )
+}
+
+fn plural(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{n} {noun}s")
+    }
+}
+
+/// `+m:ss` from the start of the session.
+fn offset(ms: i64) -> String {
+    let s = ms.max(0) / 1000;
+    format!("+{}:{:02}", s / 60, s % 60)
+}
+
+fn duration(ms: i64) -> String {
+    if ms < 1000 {
+        format!("{ms} ms")
+    } else if ms < 60_000 {
+        format!("{:.1} s", ms as f64 / 1000.0)
+    } else {
+        format!("{}m {:02}s", ms / 60_000, ms / 1000 % 60)
+    }
+}
+
+/// `YYYY-MM-DD HH:MM UTC` for milliseconds since the epoch.
+fn utc(ms: i64) -> String {
+    let secs = ms.div_euclid(1000);
+    let (days, rest) = (secs.div_euclid(86_400), secs.rem_euclid(86_400));
+    // Howard Hinnant's civil_from_days.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        rest / 3600,
+        rest % 3600 / 60
+    )
+}
@@ -0,0 +1,327 @@
+//! Append-only logs of the interaction events the book sends.
+//!
+//! Each log is a JSON Lines file in the data directory, one record per line.
+//! A record is written with a single `write` and synced to disk before the
+//! request is answered, so an acknowledged event survives a crash. A crash
+//! in the middle of a write can only leave a partial last line, which
+//! [`Store::read`] skips and the next append cuts off before writing. Any
+//! other line that is not a record, e.g. one broken by a hand edit, is
+//! skipped with a warning, and dropped when the log is next rewritten.
+//!
+//! Records only ever leave a log through [`Store::remove`] or
+//! [`Store::purge`], which rewrite it; see [`purge`](crate::purge). Appends
+//! and rewrites take a lock file in the data directory, so a purge run from
+//! the command line doesn't lose events the server stores meanwhile, and an
+//! append checked with [`Store::append_unless`] can't slip in between a
+//! purge and its audit entry.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+use rustviz_events::Event;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::purge::AuditEntry;
+
+const AUDIT_FILE: &str = "audit.jsonl";
+const LOCK_FILE: &str = ".lock";
+
+/// The logs, one per endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Log {
+    /// `POST /action/hover`: the reader moved off a tooltip trigger.
+    Hover,
+    /// `POST /action/switch`: the reader left a chapter.
+    Switch,
+    /// `POST /action/page_view`: the reader opened a chapter, in books built
+    /// with `analytics = "local"`.
+    PageView,
+}
+
+impl Log {
+    pub const ALL: [Log; 3] = [Log::Hover, Log::Switch, Log::PageView];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Log::Hover => "hover",
+            Log::Switch => "switch",
+            Log::PageView => "page_view",
+        }
+    }
+
+    fn file_name(self) -> String {
+        format!("{}.jsonl", self.name())
+    }
+}
+
+impl fmt::Display for Log {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An event and the participant that sent it, if its record has one.
+pub type FromParticipant<E> = (Option<String>, E);
+
+/// One line of a log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    /// When the server received the event, in milliseconds since the epoch.
+    pub received: u64,
+    /// The pseudonymous key of the browser that sent the event; see
+    /// [`identity`](crate::identity). Absent in records stored before
+    /// participants were tracked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub participant: Option<String>,
+    /// The event the page posted, upgraded to the schema version current
+    /// when it was received. Read it with [`rustviz_events::parse`], which
+    /// upgrades it further if needed.
+    pub event: Value,
+}
+
+impl Record {
+    pub fn new(event: Value) -> Self {
+        Record {
+            received: now_millis(),
+            participant: None,
+            event,
+        }
+    }
+}
+
+/// The data directory holding the logs.
+#[derive(Debug, Clone)]
+pub struct Store {
+    dir: PathBuf,
+}
+
+impl Store {
+    /// Opens the store in `dir`, creating the directory if needed.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).with_context(|| format!("creating {}", dir.display()))?;
+        Ok(Store { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn path(&self, log: Log) -> PathBuf {
+        self.dir.join(log.file_name())
+    }
+
+    /// Appends `record` to `log` and waits until it is on disk.
+    pub fn append(&self, log: Log, record: &Record) -> Result<()> {
+        let _lock = self.lock()?;
+        append_line(&self.path(log), record)
+    }
+
+    /// Appends `record` to `log` unless `skip` returns true, returning
+    /// whether it did. The lock is held from before `skip` is called, so no
+    /// [`Store::purge`] can run between the check and the append.
+    pub fn append_unless(
+        &self,
+        log: Log,
+        record: &Record,
+        skip: impl FnOnce() -> Result<bool>,
+    ) -> Result<bool> {
+        let _lock = self.lock()?;
+        if skip()? {
+            return Ok(false);
+        }
+        append_line(&self.path(log), record)?;
+        Ok(true)
+    }
+
+    /// Every complete record in `log`, oldest first.
+    pub fn read(&self, log: Log) -> Result<Vec<Record>> {
+        read_lines(&self.path(log))
+    }
+
+    /// Rewrites `log` without the records `remove` returns true for,
+    /// returning how many were removed.
+    pub fn remove(&self, log: Log, remove: impl FnMut(&Record) -> bool) -> Result<usize> {
+        let _lock = self.lock()?;
+        self.remove_locked(log, remove)
+    }
+
+    /// Removes the records `remove` returns true for from every log, then
+    /// appends the audit entry `audit` makes from how many were removed
+    /// from each, by log name, if it makes one. The lock is held
+    /// throughout, so appends see either the records still there or the
+    /// entry saying why they went.
+    pub fn purge(
+        &self,
+        mut remove: impl FnMut(&Record) -> bool,
+        audit: impl FnOnce(BTreeMap<String, usize>) -> Option<AuditEntry>,
+    ) -> Result<Option<AuditEntry>> {
+        let _lock = self.lock()?;
+        let mut removed = BTreeMap::new();
+        for log in Log::ALL {
+            removed.insert(log.name().to_owned(), self.remove_locked(log, &mut remove)?);
+        }
+        let entry = audit(removed);
+        if let Some(entry) = &entry {
+            append_line(&self.audit_path(), entry)?;
+        }
+        Ok(entry)
+    }
+
+    fn remove_locked(&self, log: Log, mut remove: impl FnMut(&Record) -> bool) -> Result<usize> {
+        let path = self.path(log);
+        let records = self.read(log)?;
+        let before = records.len();
+        let kept: Vec<Record> = records.into_iter().filter(|r| !remove(r)).collect();
+        if kept.len() == before {
+            return Ok(0);
+        }
+        let tmp = path.with_extension("jsonl.tmp");
+        let file = File::create(&tmp).with_context(|| format!("creating {}", tmp.display()))?;
+        let mut out = BufWriter::new(file);
+        for record in &kept {
+            serde_json::to_writer(&mut out, record)?;
+            out.write_all(b"\n")?;
+        }
+        out.into_inner()
+            .map_err(|err| err.into_error())
+            .and_then(|file| file.sync_all())
+            .with_context(|| format!("writing {}", tmp.display()))?;
+        fs::rename(&tmp, &path).with_context(|| format!("replacing {}", path.display()))?;
+        Ok(before - kept.len())
+    }
+
+    /// The audit log, `audit.jsonl`, which records every purge.
+    pub fn audit_path(&self) -> PathBuf {
+        self.dir.join(AUDIT_FILE)
+    }
+
+    /// Every entry in the audit log, oldest first.
+    pub fn audit(&self) -> Result<Vec<AuditEntry>> {
+        read_lines(&self.audit_path())
+    }
+
+    /// The events in `log` upgraded to the current schema version, and why
+    /// each of the records that could not be read was rejected.
+    pub fn events<E: Event>(&self, log: Log) -> Result<(Vec<E>, Vec<String>)> {
+        let (events, rejected) = self.participant_events(log)?;
+        Ok((events.into_iter().map(|(_, e)| e).collect(), rejected))
+    }
+
+    /// Like [`Store::events`], with the participant that sent each event.
+    pub fn participant_events<E: Event>(
+        &self,
+        log: Log,
+    ) -> Result<(Vec<FromParticipant<E>>, Vec<String>)> {
+        let mut events = Vec::new();
+        let mut rejected = Vec::new();
+        for (i, record) in self.read(log)?.into_iter().enumerate() {
+            match rustviz_events::parse(record.event) {
+                Ok(event) => events.push((record.participant, event)),
+                Err(err) => rejected.push(format!("{}:{}: {err}", self.path(log).display(), i + 1)),
+            }
+        }
+        Ok((events, rejected))
+    }
+
+    /// Blocks until no other writer, in this process or another, holds the
+    /// store's lock, and holds it until the returned file is dropped.
+    fn lock(&self) -> Result<File> {
+        let path = self.dir.join(LOCK_FILE);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .with_context(|| format!("opening {}", path.display()))?;
+        file.lock()
+            .with_context(|| format!("locking {}", path.display()))?;
+        Ok(file)
+    }
+}
+
+/// Appends `value` as a line, first cutting off a partial last line so the
+/// new one is not joined onto it. Only call with the store's lock held.
+fn append_line(path: &Path, value: &impl Serialize) -> Result<()> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .read(true)
+        .write(true)
+        .open(path)
+        .with_context(|| format!("opening {}", path.display()))?;
+    complete_lines(&mut file)
+        .and_then(|end| file.seek(SeekFrom::Start(end)))
+        .and_then(|_| file.write_all(&line))
+        .and_then(|()| file.sync_data())
+        .with_context(|| format!("writing {}", path.display()))
+}
+
+/// Truncates `file` after its last newline, if it does not end with one, and
+/// returns its length.
+fn complete_lines(file: &mut File) -> io::Result<u64> {
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Ok(0);
+    }
+    let mut last = [0];
+    file.seek(SeekFrom::Start(len - 1))?;
+    file.read_exact(&mut last)?;
+    if last[0] == b'\n' {
+        return Ok(len);
+    }
+    let mut contents = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut contents)?;
+    let end = contents
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i as u64 + 1);
+    file.set_len(end)?;
+    Ok(end)
+}
+
+fn read_lines<T: DeserializeOwned>(path: &Path) -> Result<Vec<T>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err).with_context(|| format!("opening {}", path.display())),
+    };
+    let mut values = Vec::new();
+    let mut reader = BufReader::new(file);
+    let mut line = String::new();
+    for number in 1.. {
+        line.clear();
+        if reader
+            .read_line(&mut line)
+            .with_context(|| format!("reading {}", path.display()))?
+            == 0
+        {
+            break;
+        }
+        // A line without its newline was cut short by a crash.
+        if !line.ends_with('\n') {
+            break;
+        }
+        match serde_json::from_str(&line) {
+            Ok(value) => values.push(value),
+            Err(err) => eprintln!("skipping {}:{number}: {err}", path.display()),
+        }
+    }
+    Ok(values)
+}
+
+pub(crate) fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
@@ -0,0 +1,37 @@
+//! Runs the `rustviz-server` binary on a scratch book.
+
+use std::fs;
+use std::process::Command;
+
+use tempfile::TempDir;
+
+#[test]
+fn refuses_a_private_directory_inside_the_data_directory() {
+    let root = TempDir::new().unwrap();
+    let book = root.path().join("book");
+    fs::create_dir(&book).unwrap();
+    let run = |data: &str, private: &str| {
+        Command::new(env!("CARGO_BIN_EXE_rustviz-server"))
+            .args(["--root", ".", "--data", data, "--private", private])
+            .args(["schema", "hover"])
+            .current_dir(&book)
+            .output()
+            .unwrap()
+    };
+
+    for (data, private) in [
+        ("logs", "logs/private"),
+        ("../book/logs", "logs/private"),
+        ("logs", "../book/logs/./private"),
+        ("logs", "other/../logs/private"),
+    ] {
+        let output = run(data, private);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(
+            !output.status.success() && stderr.contains("is inside the data directory"),
+            "--data {data} --private {private}: {stderr}"
+        );
+    }
+    assert!(run("logs", "../private").status.success());
+    assert!(run("logs", "logs-private").status.success());
+}
@@ -0,0 +1,108 @@
+//! Removing the records of participants who opt out and of records past the
+//! retention window, and auditing each purge.
+
+use rustviz_server::purge::{self, OptedOut, Reason};
+use rustviz_server::{Log, Record, Store};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn record(participant: Option<&str>, received: u64) -> Record {
+    Record {
+        received,
+        participant: participant.map(str::to_owned),
+        event: json!({"schema_version": 3}),
+    }
+}
+
+fn participants(store: &Store, log: Log) -> Vec<Option<String>> {
+    store
+        .read(log)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.participant)
+        .collect()
+}
+
+#[test]
+fn opting_out_removes_every_record_of_the_participant() {
+    let data = TempDir::new().unwrap();
+    let store = Store::open(data.path()).unwrap();
+    let now = Record::new(json!(null)).received;
+    for participant in [Some("a"), Some("b"), None, Some("a")] {
+        store.append(Log::Hover, &record(participant, now)).unwrap();
+    }
+    store.append(Log::Switch, &record(Some("a"), now)).unwrap();
+
+    let entry = purge::opt_out(&store, &["a".to_owned()]).unwrap();
+    assert_eq!(entry.total(), 3);
+    assert_eq!(entry.removed["hover"], 2);
+    assert_eq!(
+        participants(&store, Log::Hover),
+        [Some("b".to_owned()), None]
+    );
+    assert!(participants(&store, Log::Switch).is_empty());
+
+    // Opting out again removes nothing but is still audited.
+    assert_eq!(
+        purge::opt_out(&store, &["a".to_owned()]).unwrap().total(),
+        0
+    );
+    assert_eq!(store.audit().unwrap().len(), 2);
+    assert_eq!(
+        purge::opted_out(&store)
+            .unwrap()
+            .into_iter()
+            .collect::<Vec<_>>(),
+        ["a"]
+    );
+    let audit = std::fs::read_to_string(store.audit_path()).unwrap();
+    assert!(audit.starts_with(r#"{"time":"#));
+    assert!(audit.contains(
+        r#""reason":"opt_out","participants":["a"],"removed":{"hover":2,"page_view":0,"switch":1}}"#
+    ));
+}
+
+#[test]
+fn expiring_removes_records_past_the_retention_window() {
+    let data = TempDir::new().unwrap();
+    let store = Store::open(data.path()).unwrap();
+    let now = Record::new(json!(null)).received;
+    let day = 24 * 60 * 60 * 1000;
+    for received in [now - 40 * day, now - 20 * day, now] {
+        store
+            .append(Log::Switch, &record(Some("a"), received))
+            .unwrap();
+    }
+
+    let entry = purge::expire(&store, 30)
+        .unwrap()
+        .expect("one record is old");
+    assert_eq!(entry.total(), 1);
+    assert!(matches!(entry.reason, Reason::Retention { days: 30, .. }));
+    assert_eq!(store.read(Log::Switch).unwrap().len(), 2);
+
+    assert_eq!(purge::expire(&store, 30).unwrap(), None);
+    assert_eq!(store.audit().unwrap(), [entry]);
+    assert!(purge::opted_out(&store).unwrap().is_empty());
+}
+
+#[test]
+fn the_opted_out_set_follows_the_audit_log() {
+    let data = TempDir::new().unwrap();
+    let store = Store::open(data.path()).unwrap();
+    let mut opted_out = OptedOut::default();
+    assert!(!opted_out.contains(&store, "a").unwrap());
+
+    // An opt-out run by another process, e.g. from the command line.
+    purge::opt_out(&Store::open(data.path()).unwrap(), &["a".to_owned()]).unwrap();
+    assert!(opted_out.contains(&store, "a").unwrap());
+    assert!(!opted_out.contains(&store, "b").unwrap());
+
+    let appended = store
+        .append_unless(Log::Hover, &record(Some("a"), 0), || {
+            opted_out.contains(&store, "a")
+        })
+        .unwrap();
+    assert!(!appended);
+    assert!(store.read(Log::Hover).unwrap().is_empty());
+}
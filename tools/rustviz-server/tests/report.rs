@@ -0,0 +1,77 @@
+//! Aggregating a small hover log into the CSV and HTML reports.
+
+use rustviz_events::{parse, Hover};
+use rustviz_server::report::HoverReport;
+use serde_json::json;
+
+fn hover(session: Option<&str>, svg: &str, item: &str, lines: (i64, i64), time: i64) -> Hover {
+    let mut event = json!({
+        "schema_version": 3,
+        "svg_name": svg,
+        "hover_item": item,
+        "hover_time": time,
+        "start": 1_700_000_000_000_i64,
+        "end": 1_700_000_000_000_i64 + time,
+        "hover_message": "",
+        "start_line": lines.0,
+        "end_line": lines.1,
+    });
+    if let Some(session) = session {
+        event["session_id"] = json!(session);
+    }
+    parse(event).unwrap()
+}
+
+fn report() -> HoverReport {
+    let copy = "tl_examples/copy/input/";
+    let func = "tl_examples/func_take_ownership/input/";
+    HoverReport::new(&[
+        hover(Some("a"), copy, "event", (2, 2), 400),
+        hover(Some("a"), copy, "event", (2, 2), 600),
+        hover(Some("b"), copy, "arrow", (3, 3), 1000),
+        hover(Some("b"), func, "timeline_mut", (2, 5), 250),
+        hover(None, func, "event", (2, 2), 50),
+    ])
+}
+
+#[test]
+fn groups_hovers_by_svg_item_and_lines() {
+    let mut csv = Vec::new();
+    report().write_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert_eq!(
+        csv,
+        "\
+by,svg_name,hover_item,start_line,end_line,count,median_hover_time_ms,sessions
+svg_name,tl_examples/copy/input/,,,,3,600,2
+svg_name,tl_examples/func_take_ownership/input/,,,,2,150,1
+hover_item,,arrow,,,1,1000,1
+hover_item,,event,,,3,400,1
+hover_item,,timeline_mut,,,1,250,1
+svg_name+hover_item,tl_examples/copy/input/,arrow,,,1,1000,1
+svg_name+hover_item,tl_examples/copy/input/,event,,,2,500,1
+svg_name+hover_item,tl_examples/func_take_ownership/input/,event,,,1,50,0
+svg_name+hover_item,tl_examples/func_take_ownership/input/,timeline_mut,,,1,250,1
+svg_name+lines,tl_examples/copy/input/,,2,2,2,500,1
+svg_name+lines,tl_examples/copy/input/,,3,3,1,1000,1
+svg_name+lines,tl_examples/func_take_ownership/input/,,2,2,1,50,0
+svg_name+lines,tl_examples/func_take_ownership/input/,,2,5,1,250,1
+"
+    );
+}
+
+#[test]
+fn html_report_has_a_table_per_grouping() {
+    let report = report();
+    assert_eq!((report.events, report.sessions), (5, 2));
+    let mut html = Vec::new();
+    report.write_html(&mut html).unwrap();
+    let html = String::from_utf8(html).unwrap();
+    assert!(html.contains("<p>5 hovers from 2 sessions.</p>"));
+    assert_eq!(html.matches("<table>").count(), 4);
+    assert!(html.contains(
+        "<tr><td>tl_examples/func_take_ownership/input/</td><td>2–5</td>\
+         <td>1</td><td>250</td><td>1</td></tr>"
+    ));
+    assert!(!html.contains("<script"));
+}
@@ -0,0 +1,321 @@
+//! Runs the server on a free port against a scratch book and data directory.
+
+use std::fs;
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::thread;
+
+use serde_json::json;
+use tempfile::TempDir;
+
+use rustviz_server::identity::Pseudonymizer;
+use rustviz_server::purge;
+use rustviz_server::{Log, Record, Server, Store};
+
+struct Fixture {
+    addr: SocketAddr,
+    store: Store,
+    pseudonymizer: Pseudonymizer,
+    _dirs: (TempDir, TempDir, TempDir),
+}
+
+fn start() -> Fixture {
+    let book = TempDir::new().unwrap();
+    let data = TempDir::new().unwrap();
+    let private = TempDir::new().unwrap();
+    fs::write(book.path().join("index.html"), "<h1>Tutorial</h1>").unwrap();
+    fs::create_dir(book.path().join("ch 1")).unwrap();
+    fs::write(book.path().join("ch 1/index.html"), "chapter").unwrap();
+    fs::write(data.path().join("secret.txt"), "not served").unwrap();
+
+    let store = Store::open(data.path()).unwrap();
+    let server = Server::bind(
+        "127.0.0.1:0",
+        book.path(),
+        store.clone(),
+        Pseudonymizer::open(private.path()).unwrap(),
+    )
+    .unwrap();
+    let addr = server.addr();
+    thread::spawn(move || server.run());
+    Fixture {
+        addr,
+        store,
+        pseudonymizer: Pseudonymizer::open(private.path()).unwrap(),
+        _dirs: (book, data, private),
+    }
+}
+
+/// Sends a request with extra header lines and returns the raw response.
+fn send(addr: SocketAddr, method: &str, path: &str, headers: &str, body: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n{headers}\
+         Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+/// Sends a request and returns the status code and body.
+fn request(addr: SocketAddr, method: &str, path: &str, body: &str) -> (u16, String) {
+    let response = send(addr, method, path, "", body);
+    let status = response[9..12].parse().unwrap();
+    let body = response.split_once("\r\n\r\n").unwrap().1.to_owned();
+    (status, body)
+}
+
+/// Every file in `dir`, concatenated.
+fn contents(dir: &std::path::Path) -> String {
+    let mut all = String::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        all.push_str(&String::from_utf8_lossy(
+            &fs::read(entry.unwrap().path()).unwrap(),
+        ));
+    }
+    all
+}
+
+#[test]
+fn serves_the_book() {
+    let fixture = start();
+    assert_eq!(
+        request(fixture.addr, "GET", "/", ""),
+        (200, "<h1>Tutorial</h1>".to_owned())
+    );
+    assert_eq!(
+        request(fixture.addr, "GET", "/ch%201/?x=1", ""),
+        (200, "chapter".to_owned())
+    );
+    assert_eq!(request(fixture.addr, "GET", "/missing.html", "").0, 404);
+}
+
+#[test]
+fn does_not_serve_outside_the_book() {
+    let fixture = start();
+    let name = fixture.store.dir().file_name().unwrap().to_str().unwrap();
+    for path in [
+        format!("/../{name}/secret.txt"),
+        format!("/%2e%2e/{name}/secret.txt"),
+    ] {
+        assert_eq!(request(fixture.addr, "GET", &path, "").0, 400, "{path}");
+    }
+}
+
+#[test]
+fn records_hover_and_switch_events() {
+    let fixture = start();
+    let hover = json!({
+        "schema_version": 3,
+        "session_id": "3f2a9c1e",
+        "svg_name": "tl_examples/move_assignment/input/",
+        "hover_item": "event",
+        "hover_time": 812,
+        "start": 1_700_000_000_000_u64,
+        "end": 1_700_000_000_812_u64,
+        "hover_message": "x acquires ownership of a resource",
+        "start_line": 2,
+        "end_line": 2,
+    });
+    // An unversioned event, as sent before events had a schema.
+    let switch = json!({
+        "directory": "/ownership.html",
+        "time_elpse": 60_000,
+        "start_time": 1_700_000_000_000_u64,
+        "end_time": 1_700_000_060_000_u64,
+    });
+
+    let post =
+        |path, body: &serde_json::Value| request(fixture.addr, "POST", path, &body.to_string()).0;
+    assert_eq!(post("/action/hover", &hover), 204);
+    assert_eq!(post("/action/hover", &hover), 204);
+    assert_eq!(post("/action/switch", &switch), 204);
+
+    let hovers = fixture.store.read(Log::Hover).unwrap();
+    assert_eq!(hovers.len(), 2);
+    // The session id is stored keyed, like the browser id.
+    let mut stored = hover.clone();
+    stored["session_id"] = json!(fixture.pseudonymizer.session("3f2a9c1e"));
+    assert_ne!(stored, hover);
+    assert_eq!(hovers[0].event, stored);
+    assert_eq!(hovers[1].event, stored);
+    assert!(hovers[0].received > 0);
+    let switches = fixture.store.read(Log::Switch).unwrap();
+    assert_eq!(switches.len(), 1);
+    assert_eq!(
+        switches[0].event,
+        json!({
+            "schema_version": 3,
+            "directory": "/ownership.html",
+            "time_on_page": 60_000,
+            "start_time": 1_700_000_000_000_u64,
+            "end_time": 1_700_000_060_000_u64,
+        })
+    );
+}
+
+#[test]
+fn records_page_views() {
+    let fixture = start();
+    let view = json!({
+        "schema_version": 3,
+        "session_id": "3f2a9c1e",
+        "page_path": "/ownership.html",
+        "page_title": "Ownership - Tutorial",
+        "time": 1_700_000_000_000_u64,
+    });
+    let (status, _) = request(fixture.addr, "POST", "/action/page_view", &view.to_string());
+    assert_eq!(status, 204);
+    let mut stored = view.clone();
+    stored["session_id"] = json!(fixture.pseudonymizer.session("3f2a9c1e"));
+    assert_eq!(fixture.store.read(Log::PageView).unwrap()[0].event, stored);
+}
+
+#[test]
+fn rejects_events_that_do_not_follow_the_schema() {
+    let fixture = start();
+    assert_eq!(request(fixture.addr, "POST", "/action/hover", "{").0, 400);
+    assert_eq!(request(fixture.addr, "POST", "/action/hover", "[1]").0, 400);
+    let (status, message) = request(
+        fixture.addr,
+        "POST",
+        "/action/switch",
+        r#"{"directory": "/", "time_on_page": -1, "start_time": 0, "end_time": 0}"#,
+    );
+    assert_eq!(status, 400);
+    assert_eq!(
+        message,
+        "invalid switch event: `time_on_page` is negative (-1)"
+    );
+    assert_eq!(request(fixture.addr, "POST", "/action/close", "{}").0, 405);
+    assert!(fixture.store.read(Log::Hover).unwrap().is_empty());
+    assert!(fixture.store.read(Log::Switch).unwrap().is_empty());
+}
+
+#[test]
+fn keys_records_by_a_hash_of_the_browser_id() {
+    let fixture = start();
+    let response = send(fixture.addr, "GET", "/", "", "");
+    assert!(!response.contains("Set-Cookie"), "{response}");
+
+    let switch = json!({
+        "schema_version": 3,
+        "directory": "/",
+        "time_on_page": 1,
+        "start_time": 0,
+        "end_time": 1,
+    })
+    .to_string();
+    let response = send(fixture.addr, "POST", "/action/switch", "", &switch);
+    let cookie = response
+        .lines()
+        .find_map(|line| line.strip_prefix("Set-Cookie: rustviz_id="))
+        .expect("a browser id is issued with its first event");
+    let browser_id = cookie.split(';').next().unwrap();
+    assert!(cookie.contains("HttpOnly"));
+
+    let header = format!("Cookie: other=1; rustviz_id={browser_id}\r\n");
+    for _ in 0..2 {
+        let response = send(fixture.addr, "POST", "/action/switch", &header, &switch);
+        assert!(response.starts_with("HTTP/1.1 204"), "{response}");
+        assert!(!response.contains("Set-Cookie"));
+    }
+
+    let records = fixture.store.read(Log::Switch).unwrap();
+    let participant = records[0].participant.clone().unwrap();
+    assert_eq!(records[1].participant.as_ref(), Some(&participant));
+    assert_eq!(records[2].participant.as_ref(), Some(&participant));
+    assert_ne!(participant, browser_id);
+    assert!(!contents(fixture.store.dir()).contains(browser_id));
+}
+
+#[test]
+fn does_not_link_identities() {
+    let fixture = start();
+    let header = format!("Cookie: rustviz_id={}\r\n", "ab".repeat(16));
+    let response = send(
+        fixture.addr,
+        "POST",
+        "/action/identify",
+        &header,
+        r#"{"identity": "uniqname"}"#,
+    );
+    assert!(response.starts_with("HTTP/1.1 405"), "{response}");
+    assert!(!contents(fixture.store.dir()).contains("uniqname"));
+}
+
+#[test]
+fn drops_events_from_participants_who_opted_out() {
+    let fixture = start();
+    let switch = json!({
+        "schema_version": 3,
+        "directory": "/",
+        "time_on_page": 1,
+        "start_time": 0,
+        "end_time": 1,
+    })
+    .to_string();
+    let header = format!("Cookie: rustviz_id={}\r\n", "cd".repeat(16));
+    send(fixture.addr, "POST", "/action/switch", &header, &switch);
+    let participant = fixture.store.read(Log::Switch).unwrap()[0]
+        .participant
+        .clone()
+        .unwrap();
+
+    purge::opt_out(&fixture.store, &[participant]).unwrap();
+    assert!(fixture.store.read(Log::Switch).unwrap().is_empty());
+    let response = send(fixture.addr, "POST", "/action/switch", &header, &switch);
+    assert!(response.starts_with("HTTP/1.1 204"), "{response}");
+    assert!(fixture.store.read(Log::Switch).unwrap().is_empty());
+}
+
+#[test]
+fn skips_a_record_cut_short_by_a_crash() {
+    let data = TempDir::new().unwrap();
+    let store = Store::open(data.path()).unwrap();
+    store
+        .append(Log::Switch, &Record::new(json!({"directory": "/"})))
+        .unwrap();
+    let mut file = fs::OpenOptions::new()
+        .append(true)
+        .open(store.path(Log::Switch))
+        .unwrap();
+    file.write_all(br#"{"received":1,"event":{"dire"#).unwrap();
+
+    let records = store.read(Log::Switch).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].event, json!({"directory": "/"}));
+}
+
+#[test]
+fn cuts_a_torn_line_before_appending_and_skips_unreadable_lines() {
+    let data = TempDir::new().unwrap();
+    let store = Store::open(data.path()).unwrap();
+    let path = store.path(Log::Switch);
+    fs::write(
+        &path,
+        "not a record\n{\"received\":1,\"event\":{\"directory\":\"/\"}}\n{\"received\":2,\"ev",
+    )
+    .unwrap();
+    store
+        .append(Log::Switch, &Record::new(json!({"directory": "/src"})))
+        .unwrap();
+    assert!(!fs::read_to_string(&path).unwrap().contains("\"ev{"));
+
+    let records = store.read(Log::Switch).unwrap();
+    let events: Vec<_> = records.iter().map(|r| r.event.clone()).collect();
+    assert_eq!(
+        events,
+        [json!({"directory": "/"}), json!({"directory": "/src"})]
+    );
+
+    let removed = store
+        .remove(Log::Switch, |r| r.event == json!({"directory": "/"}))
+        .unwrap();
+    assert_eq!(removed, 1);
+    assert_eq!(store.read(Log::Switch).unwrap().len(), 1);
+}
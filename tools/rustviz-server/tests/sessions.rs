@@ -0,0 +1,157 @@
+//! Rebuilding sessions from switch and hover events, against the book's own
+//! SUMMARY.md.
+
+use std::path::Path;
+
+use rustviz_events::{parse, Hover, Switch};
+use rustviz_server::sessions::Sessions;
+use rustviz_tutorial::{chapters, Layout};
+use serde_json::json;
+
+const T0: i64 = 1_700_000_000_000;
+
+fn switch(
+    session: Option<&str>,
+    directory: &str,
+    start: i64,
+    end: i64,
+) -> (Option<String>, Switch) {
+    let mut event = json!({
+        "schema_version": 3,
+        "directory": directory,
+        "time_on_page": end - start,
+        "start_time": T0 + start,
+        "end_time": T0 + end,
+    });
+    if let Some(session) = session {
+        event["session_id"] = json!(session);
+    }
+    (None, parse(event).unwrap())
+}
+
+fn hover(session: &str, start: i64, line: i64, message: &str) -> (Option<String>, Hover) {
+    let event = parse(json!({
+        "schema_version": 3,
+        "session_id": session,
+        "svg_name": "tl_examples/copy/input/",
+        "hover_item": "event",
+        "hover_time": 1500,
+        "start": T0 + start,
+        "end": T0 + start + 1500,
+        "hover_message": message,
+        "start_line": line,
+        "end_line": line,
+    }))
+    .unwrap();
+    (None, event)
+}
+
+/// The same event, from `participant`.
+fn from<E>(participant: &str, (_, event): (Option<String>, E)) -> (Option<String>, E) {
+    (Some(participant.to_owned()), event)
+}
+
+fn summary() -> Vec<chapters::SummaryEntry> {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    chapters::summary(&layout.src()).unwrap()
+}
+
+/// Sessions of tabs that predate participants.
+fn sessions() -> Sessions {
+    Sessions::new(
+        vec![
+            switch(Some("b"), "/borrowing.html", 5_000, 9_000),
+            switch(Some("a"), "/ownership.html", 65_000, 200_000),
+            switch(Some("a"), "/", 0, 65_000),
+            switch(None, "/vectors.html", 0, 1_000),
+        ],
+        vec![
+            hover("a", 70_000, 2, "x acquires ownership of a resource"),
+            hover("a", 10_000, 1, "x is initialized"),
+            hover("a", 300_000, 3, "after the last page"),
+        ],
+        &summary(),
+    )
+}
+
+#[test]
+fn orders_visits_and_places_hovers() {
+    let sessions = sessions();
+    assert_eq!(sessions.without_session, 1);
+    let ids: Vec<&str> = sessions.sessions.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, ["a", "b"]);
+
+    let a = &sessions.sessions[0];
+    let path: Vec<(Option<&str>, Option<usize>)> = a
+        .visits
+        .iter()
+        .map(|v| (v.chapter.as_deref(), v.position))
+        .collect();
+    assert_eq!(
+        path,
+        [(Some("Motivation"), Some(1)), (Some("Ownership"), Some(3))]
+    );
+    assert_eq!(a.visits[0].hovers.len(), 1);
+    assert_eq!(a.visits[1].hovers[0].start_line, Some(2));
+    assert_eq!(a.unplaced_hovers.len(), 1);
+}
+
+#[test]
+fn writes_a_readable_timeline() {
+    let mut text = Vec::new();
+    sessions().write_text(&mut text).unwrap();
+    assert_eq!(
+        String::from_utf8(text).unwrap(),
+        "\
+tab a (started 2023-11-14 22:13 UTC, 2 pages)
+    +0:00  1. Motivation, 1m 05s
+    +0:10    event in tl_examples/copy/input/ line 1, 1.5 s: x is initialized
+    +1:05  3. Ownership, 2m 15s
+    +1:10    event in tl_examples/copy/input/ line 2, 1.5 s: x acquires ownership of a resource
+  outside any page visit:
+    +5:00    event in tl_examples/copy/input/ line 3, 1.5 s: after the last page
+
+tab b (started 2023-11-14 22:13 UTC, 1 page)
+    +0:00  4. Borrowing, 4.0 s
+
+1 event without a participant or session id not shown
+"
+    );
+}
+
+#[test]
+fn tabs_of_one_participant_make_one_path() {
+    let sessions = Sessions::new(
+        vec![
+            from("p", switch(Some("t2"), "/borrowing.html", 30_000, 90_000)),
+            from("p", switch(Some("t1"), "/ownership.html", 0, 120_000)),
+            from("q", switch(Some("t3"), "/", 10_000, 20_000)),
+        ],
+        vec![
+            from("p", hover("t1", 40_000, 2, "in the first tab")),
+            from("p", hover("t2", 50_000, 1, "in the second tab")),
+        ],
+        &summary(),
+    );
+    assert_eq!(sessions.without_session, 0);
+    let ids: Vec<&str> = sessions.sessions.iter().map(|s| s.id.as_str()).collect();
+    assert_eq!(ids, ["p", "q"]);
+
+    let p = &sessions.sessions[0];
+    assert!(p.participant);
+    assert_eq!(p.tabs, ["t1", "t2"]);
+    let path: Vec<(Option<&str>, &str)> = p
+        .visits
+        .iter()
+        .map(|v| (v.tab.as_deref(), v.chapter.as_deref().unwrap()))
+        .collect();
+    assert_eq!(path, [(Some("t1"), "Ownership"), (Some("t2"), "Borrowing")]);
+    assert_eq!(p.visits[0].hovers[0].hover_message, "in the first tab");
+    assert_eq!(p.visits[1].hovers[0].hover_message, "in the second tab");
+
+    let mut text = Vec::new();
+    sessions.write_text(&mut text).unwrap();
+    assert!(String::from_utf8(text)
+        .unwrap()
+        .starts_with("participant p (started 2023-11-14 22:13 UTC, 2 pages in 2 tabs)\n"));
+}
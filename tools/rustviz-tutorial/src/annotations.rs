@@ -0,0 +1,191 @@
+//! The RustViz events written into an example's `main.rs`.
+//!
+//! `main.rs` is `source.rs` under a header of variable definitions, with
+//! the events of each line in a `// !{ ... }` comment at its end, or a
+//! `/* !{ ... } */` one after a closing brace:
+//!
+//! ```text
+//! /* --- BEGIN Variable Definitions ---
+//! Owner x;
+//! Function String::from();
+//! --- END Variable Definitions --- */
+//! fn main() {
+//!     let x = String::from("hi"); // !{ Move(String::from()->x) }
+//! } /* !{ GoOutOfScope(x) } */
+//! ```
+//!
+//! The svg_generator draws the timeline from these, so they say which
+//! source line each row of it stands for; [`lines`](crate::lines) reads
+//! them instead of working the lines out from coordinates.
+//!
+//! Most examples' `main.rs` lives upstream, and `build` brings it into the
+//! book as it is. Until it has, an example may carry a [`TRANSCRIPTION`]
+//! in the same format instead: the events read off its committed
+//! `vis_timeline.svg`. A transcription is only read here; it is never sent
+//! upstream, and a `main.rs` takes its place.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::layout::Layout;
+
+/// The file holding an example's events when it has no `main.rs`.
+pub const TRANSCRIPTION: &str = "annotations.rs";
+
+/// The line closing the header of variable definitions.
+const HEADER_END: &str = "--- END Variable Definitions --- */";
+
+/// One event, e.g. `Move(String::from()->x)` or `GoOutOfScope(x)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// The line the event is written on, counted from the end of the
+    /// header like the rows of the code panel.
+    pub line: usize,
+    /// The kind of event, e.g. `Move`.
+    pub name: String,
+    /// The variable or function the event starts at; the only one for
+    /// events like `GoOutOfScope(x)`.
+    pub from: String,
+    /// Where a move, copy or borrow goes.
+    pub to: Option<String>,
+}
+
+/// The events of a `main.rs` and the source they annotate.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotations {
+    pub events: Vec<Event>,
+    /// The lines of `main.rs` after the header with the event comments
+    /// removed: `source.rs`, plus any blank rows of the code panel.
+    pub source: Vec<String>,
+}
+
+impl Annotations {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text.lines();
+        if !lines.by_ref().any(|line| line.trim() == HEADER_END) {
+            bail!("no `{HEADER_END}` line ending the variable definitions");
+        }
+        let mut annotations = Annotations::default();
+        for (i, text) in lines.enumerate() {
+            let line = i + 1;
+            let Some((code, events, rest)) = split(text) else {
+                annotations.source.push(text.to_owned());
+                continue;
+            };
+            for event in arguments(events) {
+                let event = parse_event(line, event)
+                    .with_context(|| format!("line {line}: cannot read event `{event}`"))?;
+                annotations.events.push(event);
+            }
+            annotations.source.push(format!("{code}{rest}"));
+        }
+        Ok(annotations)
+    }
+
+    /// Reads `main.rs` in `dir`, or the [`TRANSCRIPTION`] if there is no
+    /// `main.rs`.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = Self::path(dir).unwrap_or_else(|| dir.join("main.rs"));
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    /// The file [`Annotations::load`] reads in `dir`, if there is one.
+    pub fn path(dir: &Path) -> Option<PathBuf> {
+        ["main.rs", TRANSCRIPTION]
+            .into_iter()
+            .map(|file| dir.join(file))
+            .find(|path| path.is_file())
+    }
+
+    /// The annotations of the copy of example `name` in `dir`. A modified
+    /// copy without events of its own shows the base example's.
+    pub fn for_copy(layout: &Layout, name: &str, dir: &Path) -> Result<Self> {
+        if Self::path(dir).is_some() {
+            Self::load(dir)
+        } else {
+            Self::load(&layout.example_dir(name))
+        }
+    }
+
+    /// The line of `main.rs` that holds line `line` of `source`, the text of
+    /// `source.rs`. They are the same unless `main.rs` has blank lines for
+    /// the code panel's blank rows that `source.rs` does not.
+    pub fn main_line(&self, source: &str, line: usize) -> usize {
+        let mut source = source.lines().map(str::trim_end).peekable();
+        let mut current = 0;
+        for (i, text) in self.source.iter().enumerate() {
+            let Some(expected) = source.peek() else {
+                break;
+            };
+            if text.trim().is_empty() && !expected.is_empty() {
+                continue;
+            }
+            source.next();
+            current += 1;
+            if current == line {
+                return i + 1;
+            }
+        }
+        line
+    }
+
+    /// The lines with events, in order and without repeats.
+    pub fn lines(&self) -> Vec<usize> {
+        let mut lines: Vec<usize> = self.events.iter().map(|e| e.line).collect();
+        lines.dedup();
+        lines
+    }
+}
+
+/// Splits a line into the code before its event comment, the events, and
+/// whatever follows the comment. A comment that held nothing but the
+/// events goes with them, so `x; // !{ Bind(x) }` leaves `x;` and
+/// `x; // note !{ Bind(x) }` leaves `x; // note`.
+fn split(line: &str) -> Option<(&str, &str, &str)> {
+    let start = line.find("!{")?;
+    let end = start + line[start..].find('}')?;
+    let code = line[..start].trim_end();
+    let rest = line[end + 1..].trim_start();
+    let (code, rest) = match (code.strip_suffix("/*"), rest.strip_prefix("*/")) {
+        (Some(code), Some(rest)) => (code, rest),
+        _ => (code.strip_suffix("//").unwrap_or(code), rest),
+    };
+    Some((code.trim_end(), &line[start + 2..end], rest))
+}
+
+/// The comma-separated parts of `text`, ignoring commas in parentheses.
+fn arguments(text: &str) -> Vec<&str> {
+    let (mut parts, mut depth, mut start) = (Vec::new(), 0, 0);
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+fn parse_event(line: usize, text: &str) -> Option<Event> {
+    let (name, args) = text.strip_suffix(')')?.split_once('(')?;
+    let (from, to) = match args.split_once("->") {
+        Some((from, to)) => (from, Some(to.trim().to_owned())),
+        None => (args, None),
+    };
+    Some(Event {
+        line,
+        name: name.trim().to_owned(),
+        from: from.trim().to_owned(),
+        to,
+    })
+}
@@ -0,0 +1,190 @@
+//! Regenerates an example's visualization with the rustviz checkout.
+//!
+//! For each example the steps are the same as the old `build_tutorial.sh`:
+//! copy `source.rs` upstream, make sure a `main.rs` header with event
+//! annotations exists (copying upstream's as it is, or generating one with
+//! RustvizParse if upstream has none), run the svg_generator and copy
+//! `vis_code.svg` and `vis_timeline.svg` back. The copies are then run
+//! through [`postprocess`](crate::postprocess). The book's own `main.rs`
+//! goes upstream; a [transcription](crate::annotations::TRANSCRIPTION)
+//! never does.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use anyhow::{Context, Result};
+
+use crate::annotations::TRANSCRIPTION;
+use crate::layout::{Layout, Upstream};
+use crate::manifest::Example;
+use crate::postprocess::postprocess;
+use crate::report::{ExampleReport, Status};
+use crate::rustc::Runner;
+
+/// The two files the svg_generator writes for every example.
+pub const SVG_FILES: [&str; 2] = ["vis_code.svg", "vis_timeline.svg"];
+
+/// How many trailing lines of a failed command's stderr go into the report.
+const STDERR_TAIL: usize = 20;
+
+pub struct Builder {
+    layout: Layout,
+    upstream: Upstream,
+    runner: Runner,
+}
+
+impl Builder {
+    pub fn new(layout: Layout, upstream: Upstream) -> Result<Self> {
+        Ok(Builder {
+            layout,
+            upstream,
+            runner: Runner::new()?,
+        })
+    }
+
+    pub fn build(&mut self, example: &Example) -> ExampleReport {
+        let name = &example.name;
+        let report = self.try_build(name).unwrap_or_else(|err| {
+            ExampleReport::new(name, Status::Io).with_detail(format!("{err:#}"))
+        });
+        if report.status != Status::Built {
+            return report;
+        }
+        match postprocess(&self.layout, example, &mut self.runner) {
+            Ok(stale) if stale.is_empty() => report,
+            Ok(stale) => ExampleReport::new(name, Status::StaleOverlay).with_detail(
+                stale
+                    .iter()
+                    .map(|s| format!("{}: {}", s.overlay.display(), s.stale))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            Err(err) => {
+                ExampleReport::new(name, Status::PostprocessFailed).with_detail(format!("{err:#}"))
+            }
+        }
+    }
+
+    fn try_build(&self, name: &str) -> Result<ExampleReport> {
+        let local = self.layout.example_dir(name);
+        let remote = self.upstream.example_dir(name);
+
+        let source = local.join("source.rs");
+        if !source.is_file() {
+            return Ok(ExampleReport::new(name, Status::MissingSource)
+                .with_detail(format!("{} does not exist", source.display())));
+        }
+        fs::create_dir_all(&remote).with_context(|| format!("creating {}", remote.display()))?;
+        copy(&source, &remote.join("source.rs"))?;
+
+        let header = local.join("main.rs");
+        if !header.is_file() {
+            let upstream_header = remote.join("main.rs");
+            if !upstream_header.is_file() {
+                return self.generate_header(name, &header);
+            }
+            copy(&upstream_header, &header)?;
+            // Upstream's events replace any the book transcribed from the SVG.
+            let transcription = local.join(TRANSCRIPTION);
+            if transcription.is_file() {
+                fs::remove_file(&transcription)
+                    .with_context(|| format!("removing {}", transcription.display()))?;
+            }
+        }
+        if !has_events(&header)? {
+            return Ok(
+                ExampleReport::new(name, Status::NeedsEvents).with_detail(format!(
+                    "{} has no `// !{{ ... }}` event annotations; define events and rerun",
+                    header.display()
+                )),
+            );
+        }
+        copy(&header, &remote.join("main.rs"))?;
+
+        let output = cargo(self.upstream.src(), &["run", "--", name])?;
+        if !output.status.success() {
+            return Ok(ExampleReport::new(name, Status::SvgFailed)
+                .with_detail(failure_detail("svg_generator", &output)));
+        }
+
+        let missing: Vec<&str> = SVG_FILES
+            .into_iter()
+            .filter(|file| !remote.join(file).is_file())
+            .collect();
+        if !missing.is_empty() {
+            return Ok(
+                ExampleReport::new(name, Status::MissingSvg).with_detail(format!(
+                    "svg_generator did not write {} in {}",
+                    missing.join(", "),
+                    remote.display()
+                )),
+            );
+        }
+        for file in SVG_FILES {
+            copy(&remote.join(file), &local.join(file))?;
+        }
+        Ok(ExampleReport::new(name, Status::Built))
+    }
+
+    /// Runs RustvizParse on the upstream copy of `source.rs` and brings the
+    /// resulting `main.rs` back into the book.
+    fn generate_header(&self, name: &str, header: &Path) -> Result<ExampleReport> {
+        let source: PathBuf = ["..", "examples", name, "source.rs"].iter().collect();
+        let output = cargo(&self.upstream.parser(), &["run", "--", path_arg(&source)?])?;
+        if !output.status.success() {
+            return Ok(ExampleReport::new(name, Status::HeaderFailed)
+                .with_detail(failure_detail("RustvizParse", &output)));
+        }
+        let generated = self.upstream.example_dir(name).join("main.rs");
+        if !generated.is_file() {
+            return Ok(
+                ExampleReport::new(name, Status::HeaderFailed).with_detail(format!(
+                    "RustvizParse did not write {}",
+                    generated.display()
+                )),
+            );
+        }
+        copy(&generated, header)?;
+        Ok(
+            ExampleReport::new(name, Status::NeedsEvents).with_detail(format!(
+                "generated {}; define events and rerun",
+                header.display()
+            )),
+        )
+    }
+}
+
+/// RustViz events are written as `// !{ ... }` comments in `main.rs`. A
+/// freshly generated header has none.
+fn has_events(header: &Path) -> Result<bool> {
+    let text =
+        fs::read_to_string(header).with_context(|| format!("reading {}", header.display()))?;
+    Ok(text.contains("!{"))
+}
+
+fn cargo(dir: &Path, args: &[&str]) -> Result<Output> {
+    Command::new("cargo")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .with_context(|| format!("running cargo in {}", dir.display()))
+}
+
+fn failure_detail(tool: &str, output: &Output) -> String {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let lines: Vec<&str> = stderr.lines().collect();
+    let tail = &lines[lines.len().saturating_sub(STDERR_TAIL)..];
+    format!("{tool} exited with {}\n{}", output.status, tail.join("\n"))
+}
+
+fn copy(from: &Path, to: &Path) -> Result<()> {
+    fs::copy(from, to)
+        .map(drop)
+        .with_context(|| format!("copying {} to {}", from.display(), to.display()))
+}
+
+fn path_arg(path: &Path) -> Result<&str> {
+    path.to_str()
+        .with_context(|| format!("{} is not valid UTF-8", path.display()))
+}
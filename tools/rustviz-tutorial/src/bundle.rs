@@ -0,0 +1,564 @@
+//! Packages a built book for reading offline.
+//!
+//! The built book expects a web server. It loads the SVGs through
+//! `<object data=...>`, whose documents the page's scripts cannot reach when
+//! it is opened from `file://`, depending on `analytics` in book.toml its
+//! chapters load Google Analytics and post to the `/action/*` endpoints of
+//! rustviz-server, and `book.js` asks the Rust playground which crates it
+//! has. [`bundle`] copies the book into a directory that works from disk, in
+//! labs without internet access:
+//!
+//! - every `<object>` showing an SVG becomes an `<iframe>` with the SVG
+//!   inlined in its `srcdoc`, which shares the page's origin, so tooltips and
+//!   playback still work;
+//! - the `woff2` fonts the stylesheets declare are embedded in them as
+//!   `data:` URLs, and the other formats are dropped;
+//! - telemetry is turned off in every chapter;
+//! - the lines of the scripts marked online only, from `// online only {`
+//!   to `// } online only`, are left out. `book.js` marks the code that adds
+//!   Google Analytics and talks to the playground.
+//!
+//! A page, stylesheet or script that still loads anything from the network
+//! fails the bundle, and nothing is left behind. Scripts are checked for
+//! URLs in their string literals, so a script that builds one from parts
+//! gets past the check.
+
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use roxmltree::Document;
+
+use crate::optimize::{find, relative, resolve, STYLESHEET};
+use crate::svg::{self, Edits};
+
+/// The stylesheet declaring the book's fonts, relative to the book.
+const FONTS: &str = "fonts/fonts.css";
+
+/// The first and last line of a part of a script that needs the network.
+const ONLINE_START: &str = "// online only {";
+const ONLINE_END: &str = "// } online only";
+
+/// The start of the XML namespace names scripts pass to
+/// `createElementNS`, which look like URLs but are not fetched.
+const NAMESPACES: &str = "http://www.w3.org/";
+
+/// The elements whose `src`, `href` or `data` the browser loads.
+const RESOURCE_TAGS: &[&str] = &[
+    "audio", "embed", "feimage", "iframe", "image", "img", "link", "object", "script", "source",
+    "use", "video",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BundleReport {
+    /// The HTML pages, all of which were checked.
+    pub pages: usize,
+    /// The SVGs inlined into pages.
+    pub svgs: usize,
+    /// The fonts embedded into stylesheets.
+    pub fonts: usize,
+    /// The scripts whose online-only lines were left out.
+    pub scripts: usize,
+}
+
+/// Copies the built book at `book` to `out`, which must not exist yet, and
+/// makes the copy work offline.
+pub fn bundle(book: &Path, out: &Path) -> Result<BundleReport> {
+    if !book.join("index.html").is_file() {
+        bail!(
+            "{} is not a built book; run `mdbook build` first",
+            book.display()
+        );
+    }
+    if out.exists() {
+        bail!("{} exists already", out.display());
+    }
+    if std::path::absolute(out)?.starts_with(book.canonicalize()?) {
+        bail!("{} is inside the book", out.display());
+    }
+    let report = copy_dir(book, out).and_then(|()| make_offline(out));
+    if report.is_err() {
+        let _ = fs::remove_dir_all(out);
+    }
+    report
+}
+
+fn make_offline(out: &Path) -> Result<BundleReport> {
+    let mut report = BundleReport::default();
+
+    let mut stylesheets = Vec::new();
+    find(out, &mut stylesheets, &|path| {
+        path.extension().is_some_and(|e| e == "css")
+    })?;
+    stylesheets.sort();
+    for path in stylesheets {
+        let css =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let (css, fonts) = embed_fonts(&css, path.parent().unwrap_or(out))
+            .with_context(|| format!("embedding the fonts of {}", relative(out, &path)))?;
+        if let Some(url) = css_urls(&css).find(|url| is_remote(url)) {
+            bail!("{} loads {url}", relative(out, &path));
+        }
+        if fonts > 0 {
+            fs::write(&path, css).with_context(|| format!("writing {}", path.display()))?;
+            report.fonts += fonts;
+        }
+    }
+
+    let mut scripts = Vec::new();
+    find(out, &mut scripts, &|path| {
+        path.extension().is_some_and(|e| e == "js")
+    })?;
+    scripts.sort();
+    for path in scripts {
+        let js =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let script = relative(out, &path);
+        let offline = strip_online(&js).with_context(|| format!("bundling {script}"))?;
+        if let Some(url) = script_urls(&offline).next() {
+            bail!("{script} loads {url}");
+        }
+        if offline != js {
+            fs::write(&path, offline).with_context(|| format!("writing {}", path.display()))?;
+            report.scripts += 1;
+        }
+    }
+
+    let mut pages = Vec::new();
+    find(out, &mut pages, &|path| {
+        path.extension().is_some_and(|e| e == "html")
+    })?;
+    pages.sort();
+    for path in pages {
+        let html =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let page = relative(out, &path);
+        if let Some(url) = remote_references(&html).first() {
+            bail!("{page} loads {url}");
+        }
+        let (html, svgs) =
+            offline_page(out, &page, &html).with_context(|| format!("bundling {page}"))?;
+        fs::write(&path, html).with_context(|| format!("writing {}", path.display()))?;
+        report.pages += 1;
+        report.svgs += svgs;
+    }
+    Ok(report)
+}
+
+/// `html` with its SVGs inlined and telemetry off, and the number of SVGs.
+fn offline_page(out: &Path, page: &str, html: &str) -> Result<(String, usize)> {
+    let mut edits = Edits::default();
+    let mut svgs = 0;
+    for tag in tags(html) {
+        if tag.attribute("id") == Some("rustviz-telemetry") {
+            let attributes = tag.attributes.iter().map(|&(name, value)| match name {
+                "data-analytics" => (name, "off"),
+                _ => (name, value),
+            });
+            edits.replace(tag.range.clone(), start_tag(tag.name, attributes));
+            continue;
+        }
+        let data = match tag.attribute("data") {
+            Some(data) if tag.name.eq_ignore_ascii_case("object") && data.ends_with(".svg") => data,
+            _ => continue,
+        };
+        let Some(close) = html[tag.range.end..].find("</object>") else {
+            bail!("the <object> showing {data} is not closed");
+        };
+        let path = out.join(resolve(page, data));
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        if let Some(url) = remote_references(&text).first() {
+            bail!("{data} loads {url}");
+        }
+        edits.replace(
+            tag.range.start..tag.range.end + close + "</object>".len(),
+            iframe(out, page, &tag, &text).with_context(|| format!("inlining {data}"))?,
+        );
+        svgs += 1;
+    }
+    Ok((edits.apply(html), svgs))
+}
+
+/// An `<iframe>` showing `svg` in place of the `<object>` that loaded it. It
+/// keeps the object's class and event handlers, and is sized like the SVG.
+fn iframe(out: &Path, page: &str, object: &Tag, svg: &str) -> Result<String> {
+    let doc = Document::parse(svg).context("parsing SVG")?;
+    let root = doc.root_element();
+    let size: String = ["width", "height"]
+        .into_iter()
+        .filter_map(|name| {
+            let value = root.attribute(name)?.trim_end_matches("px");
+            value.parse::<f64>().ok()?;
+            Some(format!(" {name}: {value}px;"))
+        })
+        .collect();
+
+    let mut svg = svg[svg.find("<svg").context("no <svg> element")?..].to_owned();
+    // `rustviz-tutorial optimize` moved the rules every SVG shares out
+    let import = format!("{STYLESHEET});");
+    if let Some(end) = svg.find(&import) {
+        let start = svg[..end].rfind("@import").context("reading the @import")?;
+        let path = out.join(STYLESHEET);
+        let shared =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        svg.replace_range(start..end + import.len(), &shared);
+    }
+    let fonts = if out.join(FONTS).is_file() {
+        let up = "../".repeat(page.matches('/').count());
+        format!("<link rel=\"stylesheet\" href=\"{up}{FONTS}\">")
+    } else {
+        String::new()
+    };
+    let srcdoc = format!(
+        "<!DOCTYPE html><html><head>{fonts}<style>html, body {{ margin: 0; overflow: hidden; }} \
+         svg {{ display: block; }}</style></head><body>{svg}</body></html>"
+    );
+    let srcdoc = svg::escape(&srcdoc);
+    let style = format!("border: 0;{size}");
+
+    let attributes = object
+        .attributes
+        .iter()
+        .copied()
+        .filter(|(name, _)| !matches!(*name, "type" | "data" | "style"))
+        .chain([("style", style.as_str()), ("srcdoc", srcdoc.as_str())]);
+    Ok(format!("{}</iframe>", start_tag("iframe", attributes)))
+}
+
+/// A start tag in an HTML page or SVG.
+struct Tag<'a> {
+    name: &'a str,
+    range: Range<usize>,
+    /// Values as written, without quotes; empty for attributes without one.
+    attributes: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Tag<'a> {
+    fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, value)| value)
+    }
+}
+
+/// The start tags in `text`, skipping comments and the contents of
+/// `<script>` and `<style>`.
+fn tags(text: &str) -> Vec<Tag<'_>> {
+    let bytes = text.as_bytes();
+    let mut tags = Vec::new();
+    let mut at = 0;
+    while let Some(offset) = text[at..].find('<') {
+        let start = at + offset;
+        if text[start..].starts_with("<!--") {
+            at = text[start..]
+                .find("-->")
+                .map_or(text.len(), |end| start + end + 3);
+            continue;
+        }
+        let name_end = text[start + 1..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == ':'))
+            .map_or(text.len(), |n| start + 1 + n);
+        if !bytes.get(start + 1).is_some_and(u8::is_ascii_alphabetic) {
+            at = start + 1;
+            continue;
+        }
+        let name = &text[start + 1..name_end];
+
+        let mut i = name_end;
+        let mut attributes = Vec::new();
+        loop {
+            while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                i += 1;
+            }
+            match bytes.get(i) {
+                None => break,
+                Some(b'>') => {
+                    i += 1;
+                    break;
+                }
+                Some(b'/') => {
+                    i += 1;
+                    continue;
+                }
+                Some(_) => {}
+            }
+            let attribute_start = i;
+            while bytes
+                .get(i)
+                .is_some_and(|b| !b.is_ascii_whitespace() && !matches!(b, b'=' | b'>' | b'/'))
+            {
+                i += 1;
+            }
+            let attribute = &text[attribute_start..i];
+            while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                i += 1;
+            }
+            let mut value = "";
+            if bytes.get(i) == Some(&b'=') {
+                i += 1;
+                while bytes.get(i).is_some_and(u8::is_ascii_whitespace) {
+                    i += 1;
+                }
+                match bytes.get(i) {
+                    Some(&quote @ (b'"' | b'\'')) => {
+                        let end = text[i + 1..]
+                            .find(quote as char)
+                            .map_or(text.len(), |e| i + 1 + e);
+                        value = &text[i + 1..end];
+                        i = (end + 1).min(text.len());
+                    }
+                    _ => {
+                        let value_start = i;
+                        while bytes
+                            .get(i)
+                            .is_some_and(|b| !b.is_ascii_whitespace() && *b != b'>')
+                        {
+                            i += 1;
+                        }
+                        value = &text[value_start..i];
+                    }
+                }
+            }
+            attributes.push((attribute, value));
+        }
+        tags.push(Tag {
+            name,
+            range: start..i,
+            attributes,
+        });
+
+        at = i;
+        if ["script", "style"]
+            .iter()
+            .any(|t| name.eq_ignore_ascii_case(t))
+        {
+            if let Some(end) = text[i..].find(&format!("</{name}")) {
+                at = i + end;
+            }
+        }
+    }
+    tags
+}
+
+fn start_tag<'a>(name: &str, attributes: impl IntoIterator<Item = (&'a str, &'a str)>) -> String {
+    let mut tag = format!("<{name}");
+    for (attribute, value) in attributes {
+        if value.is_empty() {
+            tag.push_str(&format!(" {attribute}"));
+        } else if value.contains('"') {
+            tag.push_str(&format!(" {attribute}='{value}'"));
+        } else {
+            tag.push_str(&format!(" {attribute}=\"{value}\""));
+        }
+    }
+    tag.push('>');
+    tag
+}
+
+/// The URLs in `text` that the browser would fetch from the network.
+fn remote_references(text: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    for tag in tags(text) {
+        if !RESOURCE_TAGS
+            .iter()
+            .any(|t| tag.name.eq_ignore_ascii_case(t))
+        {
+            continue;
+        }
+        for (name, value) in &tag.attributes {
+            let loaded = ["src", "href", "xlink:href", "data", "poster"]
+                .iter()
+                .any(|a| name.eq_ignore_ascii_case(a));
+            if loaded && is_remote(value) {
+                found.push(value.to_string());
+            }
+        }
+    }
+    found.extend(
+        css_urls(text)
+            .filter(|url| is_remote(url))
+            .map(str::to_owned),
+    );
+    found
+}
+
+fn is_remote(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    url.starts_with("http:") || url.starts_with("https:") || url.starts_with("//")
+}
+
+/// `js` without its online-only lines, the markers included.
+fn strip_online(js: &str) -> Result<String> {
+    let mut out = String::with_capacity(js.len());
+    let mut online = false;
+    for (i, line) in js.split_inclusive('\n').enumerate() {
+        if line.contains(ONLINE_START) {
+            if online {
+                bail!("line {}: `{ONLINE_START}` inside another", i + 1);
+            }
+            online = true;
+        } else if line.contains(ONLINE_END) {
+            if !online {
+                bail!("line {}: `{ONLINE_END}` without a start", i + 1);
+            }
+            online = false;
+        } else if !online {
+            out.push_str(line);
+        }
+    }
+    if online {
+        bail!("`{ONLINE_START}` is never closed");
+    }
+    Ok(out)
+}
+
+/// The remote URLs a script has in string literals, other than namespaces.
+fn script_urls(js: &str) -> impl Iterator<Item = &str> {
+    js.match_indices(['"', '\'', '`'])
+        .filter_map(|(at, quote)| {
+            let rest = &js[at + 1..];
+            let literal = &rest[..rest.find(quote)?];
+            let (_, host) = literal.split_once("//")?;
+            let remote = is_remote(literal)
+                && !literal.starts_with(char::is_whitespace)
+                && host.starts_with(|c: char| c.is_ascii_alphanumeric())
+                && !literal.starts_with(NAMESPACES);
+            remote.then_some(literal)
+        })
+}
+
+/// The URLs of `url(...)` and `@import "..."` in a stylesheet.
+fn css_urls(css: &str) -> impl Iterator<Item = &str> {
+    let urls = css.split("url(").skip(1).filter_map(|rest| {
+        let (url, _) = rest.split_once(')')?;
+        Some(url.trim().trim_matches(['"', '\'']))
+    });
+    let imports = css.split("@import").skip(1).filter_map(|rest| {
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|c| matches!(c, '"' | '\''))?;
+        rest[1..].split(quote).next()
+    });
+    urls.chain(imports)
+}
+
+/// `css` with the `woff2` fonts of its `@font-face` rules embedded as
+/// `data:` URLs and other formats dropped, and the number embedded. URLs are
+/// relative to `dir`.
+fn embed_fonts(css: &str, dir: &Path) -> Result<(String, usize)> {
+    let mut out = String::with_capacity(css.len());
+    let mut fonts = 0;
+    let mut rest = css;
+    while let Some(at) = rest.find("@font-face") {
+        let Some(open) = rest[at..].find('{').map(|o| at + o + 1) else {
+            break;
+        };
+        let Some(close) = rest[open..].find('}').map(|c| open + c) else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        let mut declarations = Vec::new();
+        for declaration in rest[open..close].split(';') {
+            let value = match declaration.split_once(':') {
+                Some((name, value)) if name.trim() == "src" => value,
+                _ => {
+                    declarations.push(declaration.to_owned());
+                    continue;
+                }
+            };
+            let mut sources = Vec::new();
+            for source in split_sources(value) {
+                let url = match source.strip_prefix("url(") {
+                    Some(url) => url.split(')').next().unwrap_or_default(),
+                    None => {
+                        sources.push(source.to_owned());
+                        continue;
+                    }
+                };
+                let url = url.trim().trim_matches(['"', '\'']);
+                if url.starts_with("data:") || is_remote(url) {
+                    sources.push(source.to_owned());
+                    continue;
+                }
+                let file = url.split(['?', '#']).next().unwrap_or_default();
+                if !file.ends_with(".woff2") {
+                    continue;
+                }
+                let path = dir.join(file);
+                let font =
+                    fs::read(&path).with_context(|| format!("reading {}", path.display()))?;
+                sources.push(format!(
+                    "url(data:font/woff2;base64,{}) format('woff2')",
+                    base64(&font)
+                ));
+                fonts += 1;
+            }
+            if !sources.is_empty() {
+                let indent = &declaration[..declaration.len() - declaration.trim_start().len()];
+                declarations.push(format!("{indent}src: {}", sources.join(", ")));
+            }
+        }
+        out.push_str(&declarations.join(";"));
+        rest = &rest[close..];
+    }
+    out.push_str(rest);
+    Ok((out, fonts))
+}
+
+/// The comma-separated entries of a `src` descriptor, trimmed.
+fn split_sources(value: &str) -> Vec<&str> {
+    let mut sources = Vec::new();
+    let (mut depth, mut quote, mut start) = (0, None, 0);
+    for (i, c) in value.char_indices() {
+        match (c, quote) {
+            (_, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) => quote = Some(c),
+            ('(', None) => depth += 1,
+            (')', None) => depth -= 1,
+            (',', None) if depth == 0 => {
+                sources.push(value[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    sources.push(value[start..].trim());
+    sources.retain(|s| !s.is_empty());
+    sources
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | u32::from(b) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to).with_context(|| format!("creating {}", to.display()))?;
+    for entry in fs::read_dir(from).with_context(|| format!("reading {}", from.display()))? {
+        let path = entry?.path();
+        let target = to.join(path.file_name().unwrap_or_default());
+        if path.is_dir() {
+            copy_dir(&path, &target)?;
+        } else {
+            fs::copy(&path, &target).with_context(|| format!("copying {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,95 @@
+//! A printable `vis_timeline.svg`, with a numbered marker on every event.
+//!
+//! Printed pages cannot show tooltips, so [`postprocess`](crate::postprocess)
+//! writes this copy of the timeline next to it as [`PRINT_FILE`], and the
+//! `rustviz` preprocessor prints it instead of the interactive one, with the
+//! tooltips listed under it as numbered footnotes. The events are numbered
+//! in the [order of the events](crate::event_order) in `main.rs`, like the
+//! rows of the [event table](crate::event_table).
+
+use anyhow::{Context, Result};
+use roxmltree::Document;
+
+use crate::annotations::Annotations;
+use crate::event_order;
+use crate::lines;
+use crate::svg::{self, Edits};
+use crate::timeline::{Kind, Timeline};
+
+/// The printable copy of `vis_timeline.svg`, in the same directory.
+pub const PRINT_FILE: &str = "vis_timeline_print.svg";
+
+/// Markers are this far apart when several sit next to the same spot.
+const MARKER_SPACING: f64 = 18.0;
+
+/// Room added on the right for the markers of the last column.
+const MARGIN: f64 = 3.0 * MARKER_SPACING;
+
+/// One numbered event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Callout {
+    /// Starts at 1.
+    pub number: usize,
+    pub lines: (usize, usize),
+    pub variable: Option<String>,
+    pub kind: Kind,
+    /// The tooltip as plain text.
+    pub description: String,
+}
+
+/// The timeline with markers, and the events they number. Labels, and
+/// elements not on a variable's column, are not numbered. Markers sit next
+/// to the element they number, wherever it is drawn.
+pub fn callouts(text: &str, annotations: &Annotations) -> Result<(String, Vec<Callout>)> {
+    let doc = Document::parse(text).context("parsing timeline SVG")?;
+    let timeline = Timeline::new(&doc);
+    let elements: Vec<_> = event_order::order(&timeline, annotations)?
+        .into_iter()
+        .filter_map(|o| {
+            let element = o.element;
+            let span = lines::vertical_span(element.node)?;
+            Some((element, element.lines?, element.column?, span))
+        })
+        .collect();
+
+    let mut callouts = Vec::new();
+    // The markers' text is styled inline, since the timeline's stylesheet
+    // would override presentation attributes.
+    let mut markers = String::new();
+    let mut taken: Vec<(usize, i64)> = Vec::new();
+    for (number, (element, (start, end), column, (top, bottom))) in (1..).zip(elements) {
+        // Next to an event, or halfway along a state or reference.
+        let y = (top + bottom) / 2.0;
+        let spot = (column, y.round() as i64);
+        let stacked = taken.iter().filter(|s| **s == spot).count();
+        taken.push(spot);
+        let x = timeline.columns[column].x + 14.0 + MARKER_SPACING * stacked as f64;
+        markers.push_str(&format!(
+            "\n    <g class=\"callout\"><circle cx=\"{x}\" cy=\"{}\" r=\"8\" fill=\"white\" stroke=\"black\" stroke-width=\"1\"/>\
+             <text x=\"{x}\" y=\"{}\" style=\"text-anchor: middle; font: 10px sans-serif; fill: black\">{number}</text></g>",
+            y - 10.0,
+            y - 6.0,
+        ));
+        callouts.push(Callout {
+            number,
+            lines: (start, end),
+            variable: timeline.variable(element).map(str::to_owned),
+            kind: element.kind,
+            description: svg::plain_text(element.tooltip()),
+        });
+    }
+
+    let root = doc.root_element();
+    let mut edits = Edits::default();
+    if let Some(width) = root
+        .attribute("width")
+        .and_then(|w| w.trim_end_matches("px").parse::<f64>().ok())
+    {
+        edits.set_attribute(root, "width", &format!("{}px", width + MARGIN));
+    }
+    edits.insert(
+        root.range().end - "</svg>".len(),
+        format!("<g id=\"callouts\">{markers}\n</g>\n"),
+    );
+    Ok((edits.apply(text), callouts))
+}
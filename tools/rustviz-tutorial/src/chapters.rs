@@ -0,0 +1,111 @@
+//! Finding the examples a chapter of the book refers to.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+
+use crate::directive::{self, Kind};
+
+/// Asset directory prefixes as they appear in chapter markdown.
+const CODE_EXAMPLES: &str = "assets/code_examples/";
+const MODIFIED_EXAMPLES: &str = "assets/modified_examples/";
+
+/// A chapter's use of an example directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub name: String,
+    /// Whether the reference points into `modified_examples`.
+    pub modified: bool,
+}
+
+/// A markdown page under `src/`.
+#[derive(Debug, Clone)]
+pub struct Chapter {
+    /// File name relative to `src/`, e.g. `borrowing.md`.
+    pub file: String,
+    pub text: String,
+}
+
+impl Chapter {
+    /// Every example directory the chapter refers to, through a
+    /// `{{#rustviz}}` or `{{#rustviz_output}}` directive or an asset path,
+    /// without duplicates.
+    pub fn references(&self) -> Result<Vec<Reference>> {
+        let mut refs: Vec<Reference> = Vec::new();
+        let mut push = |reference: Reference| {
+            if !reference.name.is_empty() && !refs.contains(&reference) {
+                refs.push(reference);
+            }
+        };
+        let directives =
+            directive::find(&self.text).with_context(|| format!("in {}", self.file))?;
+        for directive in directives {
+            push(Reference {
+                name: directive.name,
+                modified: directive.kind == Kind::Visualization { modified: true },
+            });
+        }
+        for (prefix, modified) in [(CODE_EXAMPLES, false), (MODIFIED_EXAMPLES, true)] {
+            for (start, _) in self.text.match_indices(prefix) {
+                let rest = &self.text[start + prefix.len()..];
+                let name = rest
+                    .chars()
+                    .take_while(|&c| c.is_ascii_alphanumeric() || c == '_')
+                    .collect();
+                push(Reference { name, modified });
+            }
+        }
+        Ok(refs)
+    }
+}
+
+/// A chapter as listed in `SUMMARY.md`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SummaryEntry {
+    pub title: String,
+    /// File name relative to `src/`, e.g. `borrowing.md`.
+    pub file: String,
+}
+
+/// The chapters linked from `src_dir/SUMMARY.md`, in reading order.
+pub fn summary(src_dir: &Path) -> Result<Vec<SummaryEntry>> {
+    let path = src_dir.join("SUMMARY.md");
+    let text = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+    let mut entries = Vec::new();
+    for line in text.lines() {
+        let Some((_, rest)) = line.split_once('[') else {
+            continue;
+        };
+        let Some((title, rest)) = rest.split_once("](") else {
+            continue;
+        };
+        let Some((file, _)) = rest.split_once(')') else {
+            continue;
+        };
+        if file.is_empty() {
+            continue;
+        }
+        entries.push(SummaryEntry {
+            title: title.to_owned(),
+            file: file.trim_start_matches("./").to_owned(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Reads every `.md` file directly under `src_dir`, sorted by file name.
+pub fn load(src_dir: &Path) -> Result<Vec<Chapter>> {
+    let mut chapters = Vec::new();
+    for entry in fs::read_dir(src_dir).with_context(|| format!("reading {}", src_dir.display()))? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "md") {
+            let text =
+                fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+            let file = path.file_name().unwrap().to_string_lossy().into_owned();
+            chapters.push(Chapter { file, text });
+        }
+    }
+    chapters.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(chapters)
+}
@@ -0,0 +1,174 @@
+//! Marks what the borrow checker rejects in a `compile_fail` example.
+//!
+//! The svg_generator draws a rejected program like any other: every borrow
+//! is on the timeline, including the one rustc refuses. This finds the line
+//! of each borrow checker error's primary span and draws the events and
+//! arrows on that line in an error style, with rustc's message at the start
+//! of their tooltip. The matching line of the code panel is marked too.
+//! Events are found by the lines [`lines`](crate::lines) wrote onto them, so
+//! the timeline has to be annotated first. Marked elements are left alone,
+//! so highlighting twice is a no-op.
+
+use anyhow::{Context, Result};
+use roxmltree::{Document, Node};
+
+use crate::annotations::Annotations;
+use crate::lines::LINE_START;
+use crate::rustc::Compilation;
+use crate::svg::{self, Edits};
+
+/// Class given to the timeline elements and code lines of a conflict.
+pub const ERROR_CLASS: &str = "rustc-error";
+
+const STYLE_ID: &str = "rustc-error-style";
+
+const TIMELINE_STYLE: &str = r#"<style type="text/css" id="rustc-error-style">
+        <![CDATA[
+        .rustc-error {
+            fill: #e0301e !important;
+            stroke: #e0301e !important;
+        }
+
+        circle.rustc-error {
+            r: 7px;
+        }
+
+        polyline.rustc-error {
+            stroke-dasharray: 8 4;
+        }
+        ]]>
+        </style>"#;
+
+const CODE_STYLE: &str = r#"<style type="text/css" id="rustc-error-style">
+        <![CDATA[
+        text.code.rustc-error {
+            fill: #e0301e;
+            font-weight: bold;
+        }
+        ]]>
+        </style>"#;
+
+/// Errors that come from the borrow checker and so point at an event on the
+/// timeline. Others, like hatra2's calls to functions it leaves out, do not.
+const BORROWCK_CODES: &[&str] = &[
+    "E0373", "E0382", "E0384", "E0499", "E0502", "E0503", "E0505", "E0506", "E0507", "E0596",
+    "E0597", "E0716",
+];
+
+/// One borrow checker error and the line it is about.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// The line of `main.rs`, and so of the code panel.
+    pub line: usize,
+    /// rustc's headline, e.g. "error[E0499]: cannot borrow `x` as mutable
+    /// more than once at a time".
+    pub message: String,
+}
+
+/// Whether rustc reports `code` from the borrow checker.
+pub fn is_borrowck(code: &str) -> bool {
+    BORROWCK_CODES.contains(&code)
+}
+
+/// The borrow checker errors in `compilation` of `source`, the text of
+/// `source.rs`, in the order rustc reported them.
+pub fn conflicts(
+    compilation: &Compilation,
+    annotations: &Annotations,
+    source: &str,
+) -> Vec<Conflict> {
+    compilation
+        .errors()
+        .filter(|d| d.code.as_deref().is_some_and(is_borrowck))
+        .filter_map(|d| {
+            Some(Conflict {
+                line: annotations.main_line(source, d.primary_line()?),
+                message: d.headline(),
+            })
+        })
+        .collect()
+}
+
+/// Marks the events and arrows of `vis_timeline.svg` that happen on a
+/// conflicting line.
+pub fn highlight_timeline(text: &str, conflicts: &[Conflict]) -> Result<String> {
+    let doc = Document::parse(text).context("parsing timeline SVG")?;
+    let mut edits = Edits::default();
+    let elements = ["events", "arrows"]
+        .into_iter()
+        .filter_map(|id| svg::group(&doc, id))
+        .flat_map(|group| group.children().filter(Node::is_element));
+    for node in elements {
+        if svg::has_class(node, ERROR_CLASS) {
+            continue;
+        }
+        let Some(line) = event_line(node) else {
+            continue;
+        };
+        let messages: Vec<String> = conflicts
+            .iter()
+            .filter(|c| c.line == line)
+            .map(|c| svg::tooltip_html(&c.message))
+            .collect();
+        if messages.is_empty() {
+            continue;
+        }
+        edits.add_class(node, ERROR_CLASS);
+        if let Some(attr) = node.attribute_node("data-tooltip-text") {
+            let prefix = format!("{}. ", messages.join(". "));
+            edits.insert(attr.range_value().start, svg::escape(&prefix));
+        }
+    }
+    finish(text, &doc, edits, TIMELINE_STYLE)
+}
+
+/// Marks the conflicting lines of `vis_code.svg`.
+pub fn highlight_code(text: &str, conflicts: &[Conflict]) -> Result<String> {
+    let doc = Document::parse(text).context("parsing code SVG")?;
+    let mut edits = Edits::default();
+    if let Some(code) = svg::group(&doc, "code") {
+        // Line n is the nth row, as in `helpers.js`.
+        let rows = code.children().filter(|n| svg::has_class(*n, "code"));
+        for (i, node) in rows.enumerate() {
+            if svg::has_class(node, ERROR_CLASS) {
+                continue;
+            }
+            if conflicts.iter().any(|c| c.line == i + 1) {
+                edits.add_class(node, ERROR_CLASS);
+            }
+        }
+    }
+    finish(text, &doc, edits, CODE_STYLE)
+}
+
+/// Applies `edits`, adding `style` to the `<defs>` the first time anything
+/// is marked.
+fn finish(text: &str, doc: &Document, mut edits: Edits, style: &str) -> Result<String> {
+    if edits.is_empty() {
+        return Ok(text.to_owned());
+    }
+    let has_style = doc
+        .descendants()
+        .any(|n| n.has_tag_name("style") && n.attribute("id") == Some(STYLE_ID));
+    if !has_style {
+        let defs = doc
+            .descendants()
+            .find(|n| n.has_tag_name("defs"))
+            .context("SVG has no <defs>")?;
+        let end = defs.range().end - "</defs>".len();
+        edits.insert(end, format!("    {style}\n    "));
+    }
+    Ok(edits.apply(text))
+}
+
+/// The line of a timeline event, function call or arrow. Segments cover
+/// several lines and are not marked.
+fn event_line(node: Node) -> Option<usize> {
+    if !matches!(
+        node.tag_name().name(),
+        "circle" | "use" | "text" | "polyline"
+    ) {
+        return None;
+    }
+    node.attribute(LINE_START)?.parse().ok()
+}
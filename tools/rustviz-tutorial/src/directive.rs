@@ -0,0 +1,208 @@
+//! The `{{#rustviz ...}}` directives used in chapters.
+//!
+//! ```text
+//! {{#rustviz move_assignment}}
+//! {{#rustviz move_assignment modified}}
+//! {{#rustviz_output string_from_print}}
+//! {{#rustviz_output string_from_print "hello"}}
+//! {{#rustviz_error move_while_borrowed}}
+//! ```
+//!
+//! `{{#rustviz}}` shows an example's visualization; `modified` picks the
+//! hand-edited copy under `modified_examples` instead of the one under
+//! `code_examples`. `{{#rustviz_output}}` stands for what the example prints.
+//! With a quoted string it asserts that the program prints exactly that.
+//! `{{#rustviz_error}}` stands for the errors rustc reports for an example
+//! that does not compile. A directive preceded by a backslash is not
+//! expanded, and [`unescape`] takes the backslash off; a quoted string may
+//! contain `}}`.
+
+use std::ops::Range;
+
+use anyhow::{bail, Result};
+
+const OPEN: &str = "{{#";
+const CLOSE: &str = "}}";
+const KEYWORDS: &[&str] = &["rustviz", "rustviz_output", "rustviz_error"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Directive {
+    /// Byte range of the whole directive, braces included.
+    pub range: Range<usize>,
+    /// The example the directive refers to.
+    pub name: String,
+    pub kind: Kind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Kind {
+    /// `{{#rustviz name [modified]}}`
+    Visualization { modified: bool },
+    /// `{{#rustviz_output name ["expected stdout"]}}`
+    Output { expected: Option<String> },
+    /// `{{#rustviz_error name}}`
+    Error,
+}
+
+/// Finds every directive in `text`, in order.
+pub fn find(text: &str) -> Result<Vec<Directive>> {
+    let mut directives = Vec::new();
+    let mut from = 0;
+    while let Some(offset) = text[from..].find(OPEN) {
+        let start = from + offset;
+        let body_start = start + OPEN.len();
+        from = body_start;
+        let Some(keyword) = keyword(&text[body_start..]) else {
+            // Some other preprocessor's directive, e.g. `{{#include}}`.
+            continue;
+        };
+        let args_start = body_start + keyword.len();
+        let Some(len) = args_len(&text[args_start..]) else {
+            bail!("unterminated `{{{{#{keyword}` directive at byte {start}");
+        };
+        let end = args_start + len + CLOSE.len();
+        from = end;
+        if text[..start].ends_with('\\') {
+            continue;
+        }
+
+        let source = &text[start..end];
+        let mut words = words(&text[args_start..args_start + len])
+            .map_err(|err| err.context(format!("in `{source}`")))?
+            .into_iter();
+        let Some(Word::Bare(name)) = words.next() else {
+            bail!("`{source}` is missing an example name");
+        };
+        let kind = match keyword {
+            "rustviz" => {
+                let mut modified = false;
+                for word in words {
+                    match word {
+                        Word::Bare(option) if option == "modified" => modified = true,
+                        word => bail!("unexpected {word} in `{source}`"),
+                    }
+                }
+                Kind::Visualization { modified }
+            }
+            "rustviz_output" => {
+                let expected = match words.next() {
+                    None => None,
+                    Some(Word::Quoted(expected)) => Some(expected),
+                    Some(word) => bail!("unexpected {word} in `{source}`"),
+                };
+                if let Some(word) = words.next() {
+                    bail!("unexpected {word} in `{source}`");
+                }
+                Kind::Output { expected }
+            }
+            _ => {
+                if let Some(word) = words.next() {
+                    bail!("unexpected {word} in `{source}`");
+                }
+                Kind::Error
+            }
+        };
+        directives.push(Directive {
+            range: start..end,
+            name,
+            kind,
+        });
+    }
+    Ok(directives)
+}
+
+/// `text` with the backslash taken off every escaped directive, so that
+/// `\{{#rustviz name}}` reads `{{#rustviz name}}`. For the text between the
+/// directives [`find`] returns.
+pub fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut last = 0;
+    for (at, _) in text.match_indices(&format!("\\{OPEN}")) {
+        if keyword(&text[at + 1 + OPEN.len()..]).is_some() {
+            out.push_str(&text[last..at]);
+            last = at + 1;
+        }
+    }
+    out.push_str(&text[last..]);
+    out
+}
+
+/// The directive keyword `body` starts with, if it is one of ours.
+fn keyword(body: &str) -> Option<&str> {
+    let len = body
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(body.len());
+    KEYWORDS.contains(&&body[..len]).then_some(&body[..len])
+}
+
+/// How far the arguments after a directive's keyword run: up to the first
+/// `}}` outside a quoted string.
+fn args_len(args: &str) -> Option<usize> {
+    let mut quoted = false;
+    let mut chars = args.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' if quoted => {
+                chars.next();
+            }
+            _ if !quoted && args[i..].starts_with(CLOSE) => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+enum Word {
+    Bare(String),
+    Quoted(String),
+}
+
+impl std::fmt::Display for Word {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Word::Bare(word) => write!(f, "option `{word}`"),
+            Word::Quoted(word) => write!(f, "string {word:?}"),
+        }
+    }
+}
+
+/// Splits a directive body on whitespace. Double-quoted strings may contain
+/// whitespace and the escapes `\"`, `\\` and `\n`.
+fn words(body: &str) -> Result<Vec<Word>> {
+    let mut words = Vec::new();
+    let mut chars = body.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut word = String::new();
+            loop {
+                match chars.next() {
+                    None => bail!("unterminated string"),
+                    Some('"') => break,
+                    Some('\\') => match chars.next() {
+                        Some('n') => word.push('\n'),
+                        Some(c @ ('"' | '\\')) => word.push(c),
+                        Some(c) => bail!("unknown escape `\\{c}`"),
+                        None => bail!("unterminated string"),
+                    },
+                    Some(c) => word.push(c),
+                }
+            }
+            words.push(Word::Quoted(word));
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() || c == '"' {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            words.push(Word::Bare(word));
+        }
+    }
+    Ok(words)
+}
@@ -0,0 +1,106 @@
+//! The timeline's elements in the order of the events in `main.rs`.
+//!
+//! The event table, the printed callouts and the playback steps all list a
+//! timeline's elements, and they list them in this one order so that none
+//! of them can disagree with another or with the lines underlined in the
+//! code panel. Every dot, arrow and function call is matched to the event
+//! of the [annotations](crate::annotations) it draws: one on its line that
+//! names its variable, or for an arrow both ends. The svg_generator draws
+//! a column's elements in the order of its events, so the second dot on a
+//! column and line draws the second event there that names the column's
+//! variable. Events keep the order they are written in; states and struct
+//! boxes follow the events on the line they start at.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+
+use crate::annotations::{Annotations, Event};
+use crate::overlay::OVERLAY_CLASS;
+use crate::svg;
+use crate::timeline::{Element, Kind, Timeline};
+
+/// A timeline element and the event it draws.
+#[derive(Debug, Clone, Copy)]
+pub struct Ordered<'t, 'a, 'input> {
+    pub element: &'t Element<'a, 'input>,
+    /// Index into [`Annotations::events`]. `None` for states, struct boxes
+    /// and elements an overlay added.
+    pub event: Option<usize>,
+}
+
+/// The elements of `timeline` other than labels, in event order. Elements
+/// without lines come last, in the order they are drawn.
+pub fn order<'t, 'a, 'input>(
+    timeline: &'t Timeline<'a, 'input>,
+    annotations: &Annotations,
+) -> Result<Vec<Ordered<'t, 'a, 'input>>> {
+    let mut ordered = Vec::new();
+    // How many elements of each kind each column has on each line so far.
+    let mut seen: HashMap<(Kind, Option<usize>, usize), usize> = HashMap::new();
+    for element in &timeline.elements {
+        if element.kind == Kind::Label {
+            continue;
+        }
+        let event = match (element.kind, element.lines) {
+            (Kind::Event | Kind::Arrow | Kind::FunctionEvent, Some((line, _))) => {
+                let candidates = candidates(timeline, element, &annotations.events, line);
+                let seen = seen
+                    .entry((element.kind, element.column, line))
+                    .or_default();
+                let event = candidates.get(*seen).or(candidates.last()).copied();
+                *seen += 1;
+                if event.is_none() && !svg::has_class(element.node, OVERLAY_CLASS) {
+                    bail!(
+                        "the {} \"{}\" on line {line} draws none of the events main.rs has there",
+                        element.kind.description(),
+                        svg::plain_text(element.tooltip())
+                    );
+                }
+                event
+            }
+            _ => None,
+        };
+        // Elements that draw no event go after every event on the line they
+        // start at, and before those of the next line.
+        let key = match (event, element.lines) {
+            (Some(event), _) => (event, 1),
+            (None, Some((start, _))) => {
+                (annotations.events.partition_point(|e| e.line <= start), 0)
+            }
+            (None, None) => (usize::MAX, 0),
+        };
+        ordered.push((key, Ordered { element, event }));
+    }
+    ordered.sort_by_key(|(key, _)| *key);
+    Ok(ordered.into_iter().map(|(_, ordered)| ordered).collect())
+}
+
+/// The events on `line` that `element` may draw: those that name its
+/// variable, and for an arrow, whose tooltip names both of its ends, those
+/// between the two.
+fn candidates(timeline: &Timeline, element: &Element, events: &[Event], line: usize) -> Vec<usize> {
+    let Some(variable) = timeline.variable(element) else {
+        return Vec::new();
+    };
+    let names = svg::code_names(element.tooltip());
+    let named = |name: &str| names.iter().any(|n| same(n, name));
+    events
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e.line == line)
+        .filter(|(_, e)| {
+            same(variable, &e.from) || e.to.as_deref().is_some_and(|to| same(variable, to))
+        })
+        .filter(|(_, e)| {
+            element.kind != Kind::Arrow || (named(&e.from) && e.to.as_deref().is_some_and(named))
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Whether a name in the drawing and one in an event are the same variable
+/// or function; the drawing leaves out a function's `()` in places.
+fn same(drawn: &str, written: &str) -> bool {
+    drawn.trim_end_matches("()") == written.trim_end_matches("()")
+}
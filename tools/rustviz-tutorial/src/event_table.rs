@@ -0,0 +1,44 @@
+//! The events of a `vis_timeline.svg` as rows of text.
+//!
+//! What each event means is only in its tooltip, which screen readers and
+//! keyboards cannot reach inside an `<object>`. The `rustviz` preprocessor
+//! puts these rows in a table under every visualization instead. They are
+//! in the [order of the events](crate::event_order) in `main.rs`, like the
+//! playback steps and the printed callouts.
+
+use anyhow::{Context, Result};
+use roxmltree::Document;
+
+use crate::annotations::Annotations;
+use crate::event_order;
+use crate::svg;
+use crate::timeline::{Kind, Timeline};
+
+/// One tooltip trigger of the timeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Row {
+    /// The first and last source line the event covers.
+    pub lines: Option<(usize, usize)>,
+    /// The variable the event is drawn on; for arrows, the one they point
+    /// to.
+    pub variable: Option<String>,
+    pub kind: Kind,
+    /// The tooltip as plain text.
+    pub description: String,
+}
+
+/// The events of `vis_timeline.svg`, drawn from `annotations`, in event
+/// order. Variable labels are left out, since every row names its variable.
+pub fn rows(text: &str, annotations: &Annotations) -> Result<Vec<Row>> {
+    let doc = Document::parse(text).context("parsing timeline SVG")?;
+    let timeline = Timeline::new(&doc);
+    Ok(event_order::order(&timeline, annotations)?
+        .into_iter()
+        .map(|ordered| Row {
+            lines: ordered.element.lines,
+            variable: timeline.variable(ordered.element).map(str::to_owned),
+            kind: ordered.element.kind,
+            description: svg::plain_text(ordered.element.tooltip()),
+        })
+        .collect())
+}
@@ -0,0 +1,104 @@
+//! Where things live in the tutorial repository and in the rustviz checkout.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+/// Name of the manifest listing every example, relative to the book root.
+pub const MANIFEST_FILE: &str = "examples.toml";
+
+/// Paths inside the tutorial repository.
+#[derive(Debug, Clone)]
+pub struct Layout {
+    root: PathBuf,
+}
+
+impl Layout {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Layout { root: root.into() }
+    }
+
+    /// Walks up from `start` until a directory containing `book.toml` is found.
+    pub fn discover(start: &Path) -> Result<Self> {
+        let mut dir = start;
+        loop {
+            if dir.join("book.toml").is_file() {
+                return Ok(Layout::new(dir));
+            }
+            match dir.parent() {
+                Some(parent) => dir = parent,
+                None => bail!(
+                    "could not find book.toml in {} or any parent directory",
+                    start.display()
+                ),
+            }
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn manifest(&self) -> PathBuf {
+        self.root.join(MANIFEST_FILE)
+    }
+
+    /// The mdbook source directory holding the chapters.
+    pub fn src(&self) -> PathBuf {
+        self.root.join("src")
+    }
+
+    /// Where `mdbook build` writes the book.
+    pub fn book(&self) -> PathBuf {
+        self.root.join("book")
+    }
+
+    pub fn code_examples(&self) -> PathBuf {
+        self.root.join("src/assets/code_examples")
+    }
+
+    pub fn modified_examples(&self) -> PathBuf {
+        self.root.join("src/assets/modified_examples")
+    }
+
+    pub fn example_dir(&self, name: &str) -> PathBuf {
+        self.code_examples().join(name)
+    }
+
+    pub fn modified_dir(&self, name: &str) -> PathBuf {
+        self.modified_examples().join(name)
+    }
+}
+
+/// Paths inside the rustviz checkout that generates headers and SVGs.
+#[derive(Debug, Clone)]
+pub struct Upstream {
+    src: PathBuf,
+}
+
+impl Upstream {
+    /// `checkout` is the root of the rustviz repository, normally `../rustviz`.
+    pub fn new(checkout: &Path) -> Self {
+        Upstream {
+            src: checkout.join("src"),
+        }
+    }
+
+    /// Crate that runs `svg_generator` on an example, `cargo run <name>`.
+    pub fn src(&self) -> &Path {
+        &self.src
+    }
+
+    /// Crate that writes the `main.rs` header for a `source.rs`.
+    pub fn parser(&self) -> PathBuf {
+        self.src.join("RustvizParse")
+    }
+
+    pub fn examples(&self) -> PathBuf {
+        self.src.join("examples")
+    }
+
+    pub fn example_dir(&self, name: &str) -> PathBuf {
+        self.examples().join(name)
+    }
+}
@@ -0,0 +1,32 @@
+//! Tooling for the RustViz tutorial book.
+//!
+//! The book lives in `src/` next to `book.toml`; every example it visualizes
+//! has a directory under `src/assets/code_examples` and an entry in
+//! `examples.toml`. This crate reads that manifest and drives the sibling
+//! `rustviz` checkout to regenerate the SVGs.
+
+pub mod annotations;
+pub mod build;
+pub mod bundle;
+pub mod callouts;
+pub mod chapters;
+pub mod conflict;
+pub mod directive;
+pub mod event_order;
+pub mod event_table;
+pub mod layout;
+pub mod lines;
+pub mod manifest;
+pub mod optimize;
+pub mod overlay;
+pub mod postprocess;
+pub mod report;
+pub mod rustc;
+pub mod status;
+pub mod steps;
+pub mod svg;
+pub mod timeline;
+pub mod validate;
+
+pub use layout::Layout;
+pub use manifest::Manifest;
@@ -0,0 +1,123 @@
+//! The source lines of the timeline's tooltip triggers, written onto them.
+//!
+//! `helpers.js` underlines the code a trigger covers and logs its lines with
+//! each hover, and everything else that needs an element's lines reads them
+//! from `data-line-start` and `data-line-end`. They come from the example's
+//! [annotations](crate::annotations): the svg_generator draws one row of
+//! events for every line with events, top to bottom, so the n-th row holds
+//! the events of the n-th annotated line. Segments and reference brackets
+//! run from one row to another, and function logos sit next to a row.
+//!
+//! Nothing depends on how far apart the rows are, but if the drawing has a
+//! different number of rows than `main.rs` has annotated lines, or a segment
+//! ends between rows, this fails rather than guess. Labels, struct boxes
+//! and the elements an overlay adds are left alone; overlays write their own
+//! lines. Lines already written are corrected if they differ.
+
+use anyhow::{bail, Context, Result};
+use roxmltree::{Document, Node};
+
+use crate::annotations::Annotations;
+use crate::overlay::OVERLAY_CLASS;
+use crate::svg::{self, Edits};
+
+pub const LINE_START: &str = "data-line-start";
+pub const LINE_END: &str = "data-line-end";
+
+/// Annotates every tooltip trigger of `vis_timeline.svg` with its lines.
+pub fn annotate(text: &str, annotations: &Annotations) -> Result<String> {
+    let doc = Document::parse(text).context("parsing timeline SVG")?;
+    let triggers: Vec<Node> = doc
+        .descendants()
+        .filter(|n| svg::has_class(*n, "tooltip-trigger") && !svg::has_class(*n, OVERLAY_CLASS))
+        .collect();
+
+    let mut rows: Vec<f64> = triggers.iter().filter_map(|n| mark_y(*n)).collect();
+    rows.sort_by(f64::total_cmp);
+    rows.dedup();
+    let lines = annotations.lines();
+    if rows.len() != lines.len() {
+        bail!(
+            "the timeline has {} rows of events but main.rs has events on {} lines",
+            rows.len(),
+            lines.len()
+        );
+    }
+    let line_of = |y: f64| rows.iter().position(|row| *row == y).map(|i| lines[i]);
+
+    let mut edits = Edits::default();
+    for node in triggers {
+        let span = if let Some(y) = mark_y(node) {
+            line_of(y).map(|line| (line, line))
+        } else if let Some((top, bottom)) = extent(node) {
+            match (line_of(top), line_of(bottom)) {
+                (Some(start), Some(end)) => Some((start, end)),
+                _ => bail!(
+                    "{} from y = {top} to {bottom} does not start and end on rows of events",
+                    describe(node)
+                ),
+            }
+        } else if svg::has_class(node, "functionLogo") {
+            let y = number(node, "y").with_context(|| format!("{} has no y", describe(node)))?;
+            rows.iter()
+                .enumerate()
+                .min_by(|a, b| (a.1 - y).abs().total_cmp(&(b.1 - y).abs()))
+                .map(|(i, _)| (lines[i], lines[i]))
+        } else {
+            None
+        };
+        if let Some((start, end)) = span {
+            edits.set_attribute(node, LINE_START, &start.to_string());
+            edits.set_attribute(node, LINE_END, &end.to_string());
+        }
+    }
+    Ok(edits.apply(text))
+}
+
+/// The top and bottom of an element on the timeline's rows, for placing
+/// things next to it.
+pub(crate) fn vertical_span(node: Node) -> Option<(f64, f64)> {
+    mark_y(node).map(|y| (y, y)).or_else(|| extent(node))
+}
+
+/// The y of an element that marks a single row: an event dot, a function's
+/// dot or an arrow.
+fn mark_y(node: Node) -> Option<f64> {
+    match node.tag_name().name() {
+        "circle" => number(node, "cy"),
+        "use" => number(node, "y"),
+        "polyline" => node
+            .attribute("points")?
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|v| !v.is_empty())
+            .nth(1)?
+            .parse()
+            .ok(),
+        _ => None,
+    }
+}
+
+/// The top and bottom of an element spanning rows: a variable's state or a
+/// reference's bracket.
+fn extent(node: Node) -> Option<(f64, f64)> {
+    match node.tag_name().name() {
+        "line" => {
+            let (y1, y2) = (number(node, "y1")?, number(node, "y2")?);
+            Some((y1.min(y2), y1.max(y2)))
+        }
+        "path" => svg::vertical_extent(node.attribute("d")?),
+        _ => None,
+    }
+}
+
+fn number(node: Node, name: &str) -> Option<f64> {
+    node.attribute(name)?.parse().ok()
+}
+
+fn describe(node: Node) -> String {
+    format!(
+        "<{}> \"{}\"",
+        node.tag_name().name(),
+        svg::plain_text(node.attribute("data-tooltip-text").unwrap_or_default())
+    )
+}
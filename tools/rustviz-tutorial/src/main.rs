@@ -0,0 +1,249 @@
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use anyhow::{bail, Result};
+use clap::{Parser, Subcommand};
+
+use rustviz_tutorial::build::Builder;
+use rustviz_tutorial::bundle::bundle;
+use rustviz_tutorial::layout::Upstream;
+use rustviz_tutorial::manifest::Example;
+use rustviz_tutorial::optimize::optimize;
+use rustviz_tutorial::postprocess::postprocess;
+use rustviz_tutorial::report::{ExampleReport, Report, Status};
+use rustviz_tutorial::rustc::Runner;
+use rustviz_tutorial::status::{self, ExampleStatus};
+use rustviz_tutorial::validate::validate;
+use rustviz_tutorial::{Layout, Manifest};
+
+#[derive(Parser)]
+#[command(name = "rustviz-tutorial", about, version)]
+struct Cli {
+    /// Root of the tutorial book (the directory containing book.toml).
+    /// Defaults to the nearest parent of the current directory.
+    #[arg(long, global = true)]
+    root: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Cmd,
+}
+
+#[derive(Subcommand)]
+enum Cmd {
+    /// Regenerate the SVGs for the given examples, or for every example in
+    /// examples.toml that needs a visualization if none are given.
+    Build {
+        examples: Vec<String>,
+
+        /// The rustviz checkout used to generate headers and SVGs.
+        /// Defaults to `../rustviz` relative to the book root.
+        #[arg(long)]
+        rustviz: Option<PathBuf>,
+
+        /// Print the report as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Apply the post-build steps to the SVGs already in the book, for the
+    /// given examples or for every example in examples.toml.
+    Postprocess { examples: Vec<String> },
+    /// Check examples.toml against the example directories and chapters.
+    Check,
+    /// Compare each example in the book with the rustviz checkout, and each
+    /// modified example with the example it was made from.
+    Status {
+        /// The rustviz checkout to compare with. Defaults to `../rustviz`
+        /// relative to the book root; without one only modified examples are
+        /// compared.
+        #[arg(long)]
+        rustviz: Option<PathBuf>,
+
+        /// Print the statuses as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Move the stylesheet the SVGs share into one file and minify the SVGs
+    /// of the built book. Run after `mdbook build`.
+    Optimize {
+        /// The built book. Defaults to `book` next to book.toml.
+        #[arg(long)]
+        book: Option<PathBuf>,
+
+        /// Print the size report as JSON instead of a table.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Copy the built book into a directory that works when opened from
+    /// `file://` without a network: SVGs inlined, fonts embedded and
+    /// telemetry off. Run after `mdbook build`.
+    Bundle {
+        /// The built book. Defaults to `book` next to book.toml.
+        #[arg(long)]
+        book: Option<PathBuf>,
+
+        /// Where to write the bundle, which must not exist yet. Defaults to
+        /// `bundle` next to book.toml.
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<bool> {
+    let layout = match cli.root {
+        Some(root) => Layout::new(root),
+        None => Layout::discover(&std::env::current_dir()?)?,
+    };
+
+    match cli.command {
+        Cmd::Build {
+            examples,
+            rustviz,
+            json,
+        } => {
+            let manifest = Manifest::load(&layout.manifest())?;
+            let checkout = rustviz.unwrap_or_else(|| layout.root().join("../rustviz"));
+            if !checkout.is_dir() {
+                bail!("rustviz checkout not found at {}", checkout.display());
+            }
+            let mut builder = Builder::new(layout, Upstream::new(&checkout))?;
+
+            let names: Vec<String> = if examples.is_empty() {
+                manifest
+                    .examples
+                    .iter()
+                    .filter(|e| e.visualization)
+                    .map(|e| e.name.clone())
+                    .collect()
+            } else {
+                examples
+            };
+            let mut report = Report::default();
+            for name in &names {
+                eprintln!("building {name}...");
+                report.push(match manifest.get(name) {
+                    Some(example) => builder.build(example),
+                    None => ExampleReport::new(name, Status::NotInManifest),
+                });
+            }
+
+            let mut out = io::stdout().lock();
+            if json {
+                report.write_json(&mut out)?;
+            } else {
+                report.write_table(&mut out)?;
+            }
+            Ok(report.is_ok())
+        }
+        Cmd::Postprocess { examples } => {
+            let manifest = Manifest::load(&layout.manifest())?;
+            let selected: Vec<&Example> = if examples.is_empty() {
+                manifest.examples.iter().collect()
+            } else {
+                examples
+                    .iter()
+                    .map(|name| match manifest.get(name) {
+                        Some(example) => Ok(example),
+                        None => bail!("{name} is not in {}", layout.manifest().display()),
+                    })
+                    .collect::<Result<_>>()?
+            };
+            let mut runner = Runner::new()?;
+            let mut ok = true;
+            for example in selected {
+                match postprocess(&layout, example, &mut runner) {
+                    Ok(stale) => {
+                        for s in &stale {
+                            println!("{}: {}", s.overlay.display(), s.stale);
+                            ok = false;
+                        }
+                    }
+                    Err(err) => {
+                        println!("{}: {err:#}", example.name);
+                        ok = false;
+                    }
+                }
+            }
+            Ok(ok)
+        }
+        Cmd::Check => {
+            let manifest = Manifest::load(&layout.manifest())?;
+            let problems = validate(&layout, &manifest)?;
+            for problem in &problems {
+                println!("{problem}");
+            }
+            if problems.is_empty() {
+                println!("{} examples ok", manifest.examples.len());
+            }
+            Ok(problems.is_empty())
+        }
+        Cmd::Status { rustviz, json } => {
+            let manifest = Manifest::load(&layout.manifest())?;
+            let checkout = rustviz.unwrap_or_else(|| layout.root().join("../rustviz"));
+            let upstream = if checkout.is_dir() {
+                Some(Upstream::new(&checkout))
+            } else {
+                eprintln!(
+                    "rustviz checkout not found at {}; comparing modified examples only",
+                    checkout.display()
+                );
+                None
+            };
+            let mut runner = Runner::new()?;
+            let statuses = manifest
+                .examples
+                .iter()
+                .map(|example| status::status(&layout, upstream.as_ref(), example, &mut runner))
+                .collect::<Result<Vec<ExampleStatus>>>()?;
+
+            let mut out = io::stdout().lock();
+            if json {
+                status::write_json(&statuses, &mut out)?;
+            } else {
+                status::write_table(&statuses, &mut out)?;
+            }
+            Ok(statuses.iter().all(ExampleStatus::is_ok))
+        }
+        Cmd::Optimize { book, json } => {
+            let book = book.unwrap_or_else(|| layout.book());
+            let report = optimize(&book)?;
+            if report.files.is_empty() {
+                eprintln!("no SVGs to optimize in {}", book.display());
+                return Ok(true);
+            }
+            let mut out = io::stdout().lock();
+            if json {
+                report.write_json(&mut out)?;
+            } else {
+                report.write_table(&mut out)?;
+            }
+            Ok(true)
+        }
+        Cmd::Bundle { book, out } => {
+            let book = book.unwrap_or_else(|| layout.book());
+            let out = out.unwrap_or_else(|| layout.root().join("bundle"));
+            let report = bundle(&book, &out)?;
+            println!(
+                "{} pages in {}: {} SVGs inlined, {} fonts embedded, {} scripts taken offline",
+                report.pages,
+                out.display(),
+                report.svgs,
+                report.fonts,
+                report.scripts
+            );
+            println!("open {} to read it", out.join("index.html").display());
+            Ok(true)
+        }
+    }
+}
@@ -0,0 +1,61 @@
+//! The `examples.toml` manifest.
+//!
+//! ```toml
+//! [[example]]
+//! name = "move_assignment"
+//! chapter = "ownership.md"
+//! purpose = "Assignment moves ownership and drops the previously owned resource."
+//! stdout = ""
+//! modified = true
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Manifest {
+    #[serde(rename = "example", default)]
+    pub examples: Vec<Example>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Example {
+    /// Directory name under `src/assets/code_examples`.
+    pub name: String,
+    /// Page under `src/` that shows the example, e.g. `ownership.md`. `None`
+    /// for examples that are kept but not shown in the book.
+    pub chapter: Option<String>,
+    /// What the example teaches.
+    pub purpose: String,
+    /// What the program prints, if it can be run.
+    pub stdout: Option<String>,
+    /// Whether the book needs `vis_code.svg` and `vis_timeline.svg`.
+    #[serde(default = "default_true")]
+    pub visualization: bool,
+    /// Whether a hand-edited copy lives under `src/assets/modified_examples`.
+    #[serde(default)]
+    pub modified: bool,
+    /// The error code, e.g. `E0505`, that rustc is meant to reject
+    /// `source.rs` with.
+    pub compile_fail: Option<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text =
+            fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Example> {
+        self.examples.iter().find(|e| e.name == name)
+    }
+}
@@ -0,0 +1,565 @@
+//! Shrinks the SVGs of a built book.
+//!
+//! Every SVG the svg_generator writes embeds the same stylesheet, so a
+//! chapter with a dozen visualizations downloads it two dozen times. After
+//! `mdbook build`, [`optimize`] moves the rules all the SVGs start with into
+//! one stylesheet, [`STYLESHEET`], which each of them imports. It also drops
+//! the rules for the page's flex layout, which match nothing inside an SVG
+//! and are in `visualization.css` already, removes `<defs>` entries nothing
+//! refers to, and strips comments and the whitespace between elements.
+//! What is drawn does not change.
+//!
+//! Only the built book is changed. The SVGs in `src/` stay as generated, so
+//! the post-processing steps can keep editing them.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use roxmltree::{Document, Node, NodeType};
+use serde::Serialize;
+
+use crate::build::SVG_FILES;
+use crate::callouts::PRINT_FILE;
+use crate::svg::{self, Edits};
+
+/// The shared stylesheet, relative to the book.
+pub const STYLESHEET: &str = "assets/rustviz-svg.css";
+
+/// The size of one SVG, in bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileSize {
+    /// Relative to the book, with `/` separators.
+    pub path: String,
+    pub before: u64,
+    pub after: u64,
+}
+
+/// What the SVGs a page shows weigh, in bytes. `after` includes the shared
+/// stylesheet once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageSize {
+    /// Relative to the book, with `/` separators.
+    pub page: String,
+    pub svgs: usize,
+    pub before: u64,
+    pub after: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SizeReport {
+    /// Ordered by path.
+    pub files: Vec<FileSize>,
+    /// The size of [`STYLESHEET`].
+    pub stylesheet: u64,
+    /// The pages that show SVGs, ordered by path.
+    pub pages: Vec<PageSize>,
+}
+
+impl SizeReport {
+    pub fn before(&self) -> u64 {
+        self.files.iter().map(|f| f.before).sum()
+    }
+
+    /// Including the shared stylesheet.
+    pub fn after(&self) -> u64 {
+        self.files.iter().map(|f| f.after).sum::<u64>() + self.stylesheet
+    }
+
+    pub fn write_json(&self, out: &mut impl Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, self)?;
+        writeln!(out)
+    }
+
+    /// One line per page, then the totals.
+    pub fn write_table(&self, out: &mut impl Write) -> io::Result<()> {
+        let width = self
+            .pages
+            .iter()
+            .map(|p| p.page.len())
+            .chain(["page".len()])
+            .max()
+            .unwrap_or(0);
+        writeln!(
+            out,
+            "{:width$}  {:>4}  {:>9}  {:>9}",
+            "page", "SVGs", "before", "after"
+        )?;
+        for page in &self.pages {
+            writeln!(
+                out,
+                "{:width$}  {:>4}  {:>9}  {:>9}",
+                page.page,
+                page.svgs,
+                kilobytes(page.before),
+                kilobytes(page.after)
+            )?;
+        }
+        let (before, after) = (self.before(), self.after());
+        let saved = 100.0 - after as f64 * 100.0 / before.max(1) as f64;
+        writeln!(
+            out,
+            "\n{} SVGs: {} before, {} after including {} in {STYLESHEET} ({saved:.0}% smaller)",
+            self.files.len(),
+            kilobytes(before),
+            kilobytes(after),
+            kilobytes(self.stylesheet)
+        )
+    }
+}
+
+/// Optimizes every `vis_code.svg` and `vis_timeline.svg` in the built book
+/// at `book` and writes the shared stylesheet. A book whose SVGs are all
+/// optimized already is left alone, and the report is empty. The printable
+/// timelines are not changed, but count towards the pages that show them.
+pub fn optimize(book: &Path) -> Result<SizeReport> {
+    let mut paths = Vec::new();
+    find(book, &mut paths, &|path| {
+        SVG_FILES
+            .iter()
+            .any(|f| path.file_name() == Some(f.as_ref()))
+    })?;
+    paths.sort();
+    let mut svgs = Vec::new();
+    let mut optimized = 0;
+    for path in paths {
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        if text.contains(&format!("{STYLESHEET});")) {
+            optimized += 1;
+        } else {
+            svgs.push((path, text));
+        }
+    }
+    if svgs.is_empty() {
+        return Ok(SizeReport::default());
+    }
+    if optimized > 0 {
+        bail!(
+            "{optimized} SVGs in {} are optimized already but {} are not; run `mdbook build` again first",
+            book.display(),
+            svgs.len()
+        );
+    }
+
+    let sheets = svgs
+        .iter()
+        .map(|(path, text)| {
+            generator_rules(text).with_context(|| format!("reading {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let shared = shared_rules(&sheets);
+    let shared_css: String = sheets[0]
+        .iter()
+        .filter(|r| shared.contains(r.as_str()))
+        .map(|r| format!("{r}\n"))
+        .collect();
+    let stylesheet = book.join(STYLESHEET);
+    fs::write(&stylesheet, &shared_css)
+        .with_context(|| format!("writing {}", stylesheet.display()))?;
+
+    let mut report = SizeReport {
+        stylesheet: shared_css.len() as u64,
+        ..SizeReport::default()
+    };
+    let mut sizes = BTreeMap::new();
+    for ((path, text), rules) in svgs.iter().zip(&sheets) {
+        let relative = relative(book, path);
+        let depth = relative.matches('/').count();
+        let css: String = rules
+            .iter()
+            .filter(|r| !shared.contains(r.as_str()) && !is_layout(r))
+            .map(String::as_str)
+            .collect();
+        let css = format!("@import url({}{STYLESHEET});{css}", "../".repeat(depth));
+        let minified =
+            minify(text, &css).with_context(|| format!("optimizing {}", path.display()))?;
+        fs::write(path, &minified).with_context(|| format!("writing {}", path.display()))?;
+        let size = FileSize {
+            path: relative.clone(),
+            before: text.len() as u64,
+            after: minified.len() as u64,
+        };
+        sizes.insert(relative, (size.before, size.after));
+        report.files.push(size);
+    }
+
+    // The printable timelines are shown as images, which cannot import the
+    // shared stylesheet, so they keep their own; they still weigh on pages.
+    let mut printed = Vec::new();
+    find(book, &mut printed, &|path| {
+        path.file_name() == Some(PRINT_FILE.as_ref())
+    })?;
+    for path in printed {
+        let size = fs::metadata(&path)
+            .with_context(|| format!("reading {}", path.display()))?
+            .len();
+        sizes.insert(relative(book, &path), (size, size));
+    }
+
+    let mut pages = Vec::new();
+    find(book, &mut pages, &|path| {
+        path.extension().is_some_and(|e| e == "html")
+    })?;
+    pages.sort();
+    for page in pages {
+        let html =
+            fs::read_to_string(&page).with_context(|| format!("reading {}", page.display()))?;
+        let page = relative(book, &page);
+        let shown: BTreeSet<String> = ["data=\"", "src=\""]
+            .iter()
+            .flat_map(|attribute| html.split(attribute).skip(1))
+            .filter_map(|rest| rest.split_once('"'))
+            .map(|(url, _)| resolve(&page, url))
+            .filter(|svg| sizes.contains_key(svg))
+            .collect();
+        if shown.is_empty() {
+            continue;
+        }
+        report.pages.push(PageSize {
+            page,
+            svgs: shown.len(),
+            before: shown.iter().map(|s| sizes[s].0).sum(),
+            after: shown.iter().map(|s| sizes[s].1).sum::<u64>() + report.stylesheet,
+        });
+    }
+    Ok(report)
+}
+
+/// The rules of the stylesheet the svg_generator puts first in `<defs>`,
+/// minified.
+fn generator_rules(text: &str) -> Result<Vec<String>> {
+    let doc = Document::parse(text).context("parsing SVG")?;
+    let style = generator_style(&doc).context("SVG has no <style>")?;
+    Ok(rules(&minify_css(style.text().unwrap_or_default())))
+}
+
+fn generator_style<'a, 'input>(doc: &'a Document<'input>) -> Option<Node<'a, 'input>> {
+    doc.descendants().find(|n| n.has_tag_name("style"))
+}
+
+/// The rules that can move from every sheet in `sheets` to the shared
+/// stylesheet. An imported rule comes before every rule left in the SVG, so
+/// a rule found in every sheet still stays if a rule before it in some sheet
+/// stays and sets one of the same properties.
+fn shared_rules(sheets: &[Vec<String>]) -> BTreeSet<&str> {
+    let mut shared: BTreeSet<&str> = sheets[0]
+        .iter()
+        .map(String::as_str)
+        .filter(|r| !is_layout(r) && !is_local(r) && !r.starts_with('@'))
+        .filter(|r| sheets.iter().all(|s| s.iter().any(|o| o == r)))
+        .collect();
+    loop {
+        let mut kept = Vec::new();
+        for sheet in sheets {
+            let mut set_inline = BTreeSet::new();
+            for rule in sheet {
+                if !shared.contains(rule.as_str()) {
+                    set_inline.extend(properties(rule));
+                } else if properties(rule).any(|p| set_inline.contains(p)) {
+                    kept.push(rule.as_str());
+                }
+            }
+        }
+        if kept.is_empty() {
+            return shared;
+        }
+        for rule in kept {
+            shared.remove(rule);
+        }
+    }
+}
+
+/// The properties a minified rule sets.
+fn properties(rule: &str) -> impl Iterator<Item = &str> {
+    let body = rule
+        .split_once('{')
+        .map_or("", |(_, body)| body.trim_end_matches('}'));
+    body.split(';')
+        .filter_map(|declaration| declaration.split_once(':'))
+        .map(|(property, _)| property)
+}
+
+/// Rules for the `<object>`s and `.flex-container` of the page around the
+/// SVG.
+fn is_layout(rule: &str) -> bool {
+    let selectors = rule.split('{').next().unwrap_or_default();
+    selectors
+        .split(',')
+        .all(|s| s.starts_with(".flex-container") || s.starts_with("object"))
+}
+
+/// Rules that refer to an element of the SVG with `url(#...)`. They stay in
+/// the SVG, since a URL in an imported stylesheet is resolved against the
+/// stylesheet.
+fn is_local(rule: &str) -> bool {
+    rule.contains("url(#")
+}
+
+/// `text` with its first stylesheet replaced by `css`, the others minified,
+/// and unused `<defs>`, comments and whitespace between elements removed.
+fn minify(text: &str, css: &str) -> Result<String> {
+    let doc = Document::parse(text).context("parsing SVG")?;
+    let generator = generator_style(&doc);
+    let mut edits = Edits::default();
+    let mut removed: Vec<Range<usize>> = Vec::new();
+    if let Some(defs) = doc.descendants().find(|n| n.has_tag_name("defs")) {
+        for def in defs
+            .children()
+            .filter(|n| n.is_element() && !n.has_tag_name("style"))
+        {
+            let Some(id) = def.attribute("id") else {
+                continue;
+            };
+            let range = def.range();
+            if ![&text[..range.start], &text[range.end..], css]
+                .iter()
+                .any(|t| refers_to(t, id))
+            {
+                edits.replace(range.clone(), "");
+                removed.push(range);
+            }
+        }
+    }
+    for node in doc.descendants() {
+        let range = node.range();
+        if removed
+            .iter()
+            .any(|r| r.start <= range.start && range.end <= r.end)
+        {
+            continue;
+        }
+        match node.node_type() {
+            NodeType::Comment => {
+                edits.replace(range.clone(), "");
+                removed.push(range);
+            }
+            NodeType::Text if is_blank(node) && !in_text(node) => edits.replace(range, ""),
+            NodeType::Element if node.has_tag_name("style") => {
+                let css = match generator {
+                    Some(g) if g == node => css.to_owned(),
+                    _ => minify_css(node.text().unwrap_or_default()),
+                };
+                let id = node
+                    .attribute("id")
+                    .map(|id| format!(" id=\"{}\"", svg::escape(id)))
+                    .unwrap_or_default();
+                edits.replace(
+                    range.clone(),
+                    format!("<style type=\"text/css\"{id}><![CDATA[{css}]]></style>"),
+                );
+                removed.push(range);
+            }
+            NodeType::Element => {
+                let end = start_tag_end(text, range.start);
+                let tag = &text[range.start..end];
+                let compact = compact_tag(tag);
+                if compact != tag {
+                    edits.replace(range.start..end, compact);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(edits.apply(text).trim().to_owned())
+}
+
+fn is_blank(node: Node) -> bool {
+    node.text().is_some_and(|t| t.trim().is_empty())
+}
+
+/// Whether whitespace in `node` is drawn.
+fn in_text(node: Node) -> bool {
+    node.ancestors().any(|a| {
+        matches!(
+            a.tag_name().name(),
+            "text" | "tspan" | "textPath" | "desc" | "title"
+        )
+    })
+}
+
+/// Whether `text` has a `#id` reference, e.g. `href="#id"` or `url(#id)`.
+fn refers_to(text: &str, id: &str) -> bool {
+    let reference = format!("#{id}");
+    text.match_indices(&reference).any(|(i, _)| {
+        !text[i + reference.len()..]
+            .starts_with(|c: char| c.is_alphanumeric() || c == '-' || c == '_')
+    })
+}
+
+/// Just past the `>` of the start tag at `start`.
+fn start_tag_end(text: &str, start: usize) -> usize {
+    let mut quote = None;
+    for (i, c) in text[start..].char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return start + i + 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+/// A start tag with one space between attributes and none before its end.
+/// Values are left as they are.
+fn compact_tag(tag: &str) -> String {
+    let mut out = String::with_capacity(tag.len());
+    let mut quote = None;
+    let mut space = false;
+    for c in tag.chars() {
+        match (quote, c) {
+            (Some(q), c) => {
+                if c == q {
+                    quote = None;
+                }
+            }
+            (None, c) if c.is_whitespace() => {
+                space = true;
+                continue;
+            }
+            (None, c) => {
+                if space && !matches!(c, '>' | '/' | '=') && !out.ends_with('=') {
+                    out.push(' ');
+                }
+                space = false;
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+            }
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// `css` without comments or whitespace that doesn't matter.
+fn minify_css(css: &str) -> String {
+    // No space is needed next to these. `:` separates a property from its
+    // value only inside a block; in a selector it may follow a descendant
+    // combinator.
+    let tight =
+        |c: char, depth: usize| matches!(c, '{' | '}' | ';' | ',' | '>') || (c == ':' && depth > 0);
+    let mut out = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    let mut quote = None;
+    let mut depth = 0;
+    let mut space = false;
+    while let Some(c) = chars.next() {
+        if let Some(q) = quote {
+            out.push(c);
+            if c == q {
+                quote = None;
+            }
+            continue;
+        }
+        if c == '/' && chars.peek() == Some(&'*') {
+            chars.next();
+            let mut previous = ' ';
+            for c in chars.by_ref() {
+                if previous == '*' && c == '/' {
+                    break;
+                }
+                previous = c;
+            }
+            space = true;
+            continue;
+        }
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        let after_tight = out.chars().next_back().is_some_and(|l| tight(l, depth));
+        if space && !out.is_empty() && !tight(c, depth) && !after_tight {
+            out.push(' ');
+        }
+        space = false;
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth = depth.saturating_sub(1);
+                if out.ends_with(';') {
+                    out.pop();
+                }
+            }
+            '"' | '\'' => quote = Some(c),
+            _ => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// The top-level rules of minified `css`.
+fn rules(css: &str) -> Vec<String> {
+    let mut rules = Vec::new();
+    let mut quote = None;
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in css.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    rules.push(css[start..=i].to_owned());
+                    start = i + 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    rules
+}
+
+pub(crate) fn find(
+    dir: &Path,
+    found: &mut Vec<PathBuf>,
+    wanted: &dyn Fn(&Path) -> bool,
+) -> Result<()> {
+    for entry in fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find(&path, found, wanted)?;
+        } else if wanted(&path) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// `path` relative to `base`, with `/` separators.
+pub(crate) fn relative(base: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(base).unwrap_or(path);
+    let parts: Vec<_> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect();
+    parts.join("/")
+}
+
+/// The book path a relative URL on `page` points to.
+pub(crate) fn resolve(page: &str, url: &str) -> String {
+    let mut parts: Vec<&str> = page.split('/').collect();
+    parts.pop();
+    for component in Path::new(url).components() {
+        match component {
+            Component::ParentDir => {
+                parts.pop();
+            }
+            Component::Normal(part) => parts.push(part.to_str().unwrap_or_default()),
+            _ => {}
+        }
+    }
+    parts.join("/")
+}
+
+fn kilobytes(bytes: u64) -> String {
+    format!("{:.1} kB", bytes as f64 / 1000.0)
+}
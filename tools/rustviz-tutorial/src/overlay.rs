@@ -0,0 +1,292 @@
+//! Hand edits to a generated timeline, kept next to it in `overlay.toml`.
+//!
+//! Editing `vis_timeline.svg` directly does not survive regenerating it or
+//! running `copy_assets.sh`. An overlay instead names the events it changes
+//! by source line, variable and kind, and is applied again after every
+//! build:
+//!
+//! ```toml
+//! # Change or extend the tooltip of an existing element.
+//! [[tooltip]]
+//! line = 4
+//! variable = "y"
+//! kind = "event"                # the default
+//! contains = "acquires"         # only needed if several elements match
+//! append = "; the resource previously owned is dropped"
+//! # or: text = "replaces the whole tooltip"
+//!
+//! # Draw an event the svg_generator cannot express.
+//! [[event]]
+//! line = 4
+//! variable = "y"
+//! text = "`y`'s old resource is dropped"
+//!
+//! # Write a note next to a variable's timeline.
+//! [[label]]
+//! line = 4
+//! variable = "y"
+//! text = "drop"
+//! ```
+//!
+//! Text in backticks is set in the code font, like variable names in the
+//! generated tooltips. Entries that no longer match anything in the SVG are
+//! reported as stale instead of being dropped silently.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use roxmltree::Document;
+use serde::Deserialize;
+
+use crate::lines::{LINE_END, LINE_START};
+use crate::svg::{self, Edits};
+use crate::timeline::{self, Element, Kind, Timeline};
+
+/// Name of the overlay file in an example directory.
+pub const OVERLAY_FILE: &str = "overlay.toml";
+
+/// Class of the elements an overlay adds, so they are recognized when it is
+/// applied again.
+pub(crate) const OVERLAY_CLASS: &str = "overlay";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Overlay {
+    #[serde(default, rename = "tooltip")]
+    pub tooltips: Vec<Tooltip>,
+    #[serde(default, rename = "event")]
+    pub events: Vec<Event>,
+    #[serde(default, rename = "label")]
+    pub labels: Vec<Label>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tooltip {
+    pub line: usize,
+    pub variable: String,
+    #[serde(default = "default_kind")]
+    pub kind: Kind,
+    /// Picks among several elements of the same kind on the same line and
+    /// column, e.g. the two events of a borrow that starts and ends on the
+    /// same line.
+    pub contains: Option<String>,
+    /// Replaces the tooltip.
+    pub text: Option<String>,
+    /// Added to the end of the tooltip.
+    pub append: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Event {
+    pub line: usize,
+    pub variable: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Label {
+    pub line: usize,
+    pub variable: String,
+    pub text: String,
+}
+
+fn default_kind() -> Kind {
+    Kind::Event
+}
+
+/// An overlay entry that does not apply to the SVG any more.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Stale {
+    /// Which entry, e.g. "tooltip for the event of `y` on line 4".
+    pub entry: String,
+    pub reason: String,
+}
+
+impl fmt::Display for Stale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.entry, self.reason)
+    }
+}
+
+impl Overlay {
+    /// Reads the overlay in `dir`, if it has one.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(OVERLAY_FILE);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let overlay: Overlay =
+            toml::from_str(&text).with_context(|| format!("parsing {}", path.display()))?;
+        for tooltip in &overlay.tooltips {
+            if tooltip.text.is_some() == tooltip.append.is_some() {
+                bail!(
+                    "{}: {} needs exactly one of `text` and `append`",
+                    path.display(),
+                    tooltip.describe()
+                );
+            }
+        }
+        Ok(Some(overlay))
+    }
+
+    /// Applies the overlay to the text of a `vis_timeline.svg`. Applying it
+    /// to its own output changes nothing.
+    pub fn apply(&self, text: &str) -> Result<(String, Vec<Stale>)> {
+        let doc = Document::parse(text).context("parsing timeline SVG")?;
+        let timeline = Timeline::new(&doc);
+        let mut edits = Edits::default();
+        let mut stale = Vec::new();
+
+        for tooltip in &self.tooltips {
+            match tooltip.find(&timeline) {
+                Ok(element) => {
+                    let current = element.tooltip();
+                    let value = element
+                        .node
+                        .attribute_node("data-tooltip-text")
+                        .expect("elements have a tooltip")
+                        .range_value();
+                    // Appending leaves the generated text exactly as it was.
+                    match (&tooltip.text, &tooltip.append) {
+                        (Some(text), _) => {
+                            let text = svg::tooltip_html(text);
+                            if current != text {
+                                edits.replace(value, svg::escape(&text));
+                            }
+                        }
+                        (None, Some(append)) => {
+                            let append = svg::tooltip_html(append);
+                            if !current.ends_with(&append) {
+                                edits.insert(value.end, svg::escape(&append));
+                            }
+                        }
+                        (None, None) => unreachable!("checked when loading"),
+                    }
+                }
+                Err(reason) => stale.push(Stale {
+                    entry: tooltip.describe(),
+                    reason,
+                }),
+            }
+        }
+
+        let added = |kind: &str, line: usize, variable: &str| {
+            doc.descendants().any(|n| {
+                svg::has_class(n, OVERLAY_CLASS)
+                    && n.attribute("data-overlay") == Some(&format!("{kind} {line} {variable}"))
+            })
+        };
+        let mut new_events = String::new();
+        for event in &self.events {
+            let entry = format!("event of `{}` on line {}", event.variable, event.line);
+            let Some(column) = timeline.column(&event.variable) else {
+                stale.push(no_variable(entry, &event.variable));
+                continue;
+            };
+            if added("event", event.line, &event.variable) {
+                continue;
+            }
+            let column = &timeline.columns[column];
+            new_events.push_str(&format!(
+                "\n        <circle cx=\"{}\" cy=\"{}\" r=\"5\" data-hash=\"{}\" class=\"tooltip-trigger {OVERLAY_CLASS}\" data-overlay=\"event {} {}\" data-tooltip-text=\"{}\" {LINE_START}=\"{line}\" {LINE_END}=\"{line}\"/>",
+                column.x,
+                timeline::event_y(event.line),
+                column.hash.as_deref().unwrap_or("0"),
+                event.line,
+                svg::escape(&event.variable),
+                svg::escape(&svg::tooltip_html(&event.text)),
+                line = event.line,
+            ));
+        }
+        for label in &self.labels {
+            let entry = format!("label of `{}` on line {}", label.variable, label.line);
+            let Some(column) = timeline.column(&label.variable) else {
+                stale.push(no_variable(entry, &label.variable));
+                continue;
+            };
+            if added("label", label.line, &label.variable) {
+                continue;
+            }
+            let column = &timeline.columns[column];
+            new_events.push_str(&format!(
+                "\n        <text x=\"{}\" y=\"{}\" data-hash=\"{}\" class=\"label tooltip-trigger {OVERLAY_CLASS}\" style=\"font-size: 0.75em\" data-overlay=\"label {} {}\" data-tooltip-text=\"{}\">{}</text>",
+                column.x + 12.0,
+                timeline::event_y(label.line) + 4,
+                column.hash.as_deref().unwrap_or("0"),
+                label.line,
+                svg::escape(&label.variable),
+                svg::escape(&svg::tooltip_html(&label.text)),
+                svg::escape(&label.text.replace('`', "")),
+            ));
+        }
+        if !new_events.is_empty() {
+            let events = svg::group(&doc, "events").context("timeline has no events group")?;
+            let end = events.range().end - "</g>".len();
+            // Keep the closing tag on its own, indented line.
+            let indent = text[..end].len() - text[..end].trim_end_matches(' ').len();
+            edits.insert(
+                end - indent,
+                format!("{}\n", new_events.trim_start_matches('\n')),
+            );
+        }
+
+        Ok((edits.apply(text), stale))
+    }
+}
+
+impl Tooltip {
+    fn describe(&self) -> String {
+        format!(
+            "tooltip for the {} of `{}` on line {}",
+            self.kind, self.variable, self.line
+        )
+    }
+
+    /// The one element this entry is about.
+    fn find<'a, 'input>(
+        &self,
+        timeline: &Timeline<'a, 'input>,
+    ) -> std::result::Result<Element<'a, 'input>, String> {
+        let Some(column) = timeline.column(&self.variable) else {
+            return Err(format!("names `{}`, which has no timeline", self.variable));
+        };
+        let replacement = self.text.as_deref().map(svg::tooltip_html);
+        let found: Vec<Element> = timeline
+            .elements
+            .iter()
+            .filter(|e| e.kind == self.kind && e.column == Some(column))
+            .filter(|e| !svg::has_class(e.node, OVERLAY_CLASS))
+            .filter(|e| e.lines.map(|(start, _)| start) == Some(self.line))
+            .filter(|e| match &self.contains {
+                Some(contains) => {
+                    svg::plain_text(e.tooltip()).contains(contains.as_str())
+                        || replacement.as_deref() == Some(e.tooltip())
+                }
+                None => true,
+            })
+            .copied()
+            .collect();
+        match found.as_slice() {
+            [element] => Ok(*element),
+            [] => Err("matches nothing".to_owned()),
+            _ => Err(format!(
+                "matches {} elements; add `contains` to pick one",
+                found.len()
+            )),
+        }
+    }
+}
+
+fn no_variable(entry: String, variable: &str) -> Stale {
+    Stale {
+        entry,
+        reason: format!("names `{variable}`, which has no timeline"),
+    }
+}
@@ -0,0 +1,159 @@
+//! Steps applied to an example's SVGs after the svg_generator writes them.
+//!
+//! `rustviz-tutorial build` runs them after every regeneration and
+//! `rustviz-tutorial postprocess` runs them on the SVGs already in the tree,
+//! e.g. after `copy_assets.sh` brought in new ones. Every step leaves its own
+//! output unchanged, so running them again is harmless.
+//!
+//! The steps, in order:
+//!
+//! 1. write the source [lines](crate::lines) of each element onto the
+//!    timeline, from the events annotated in `main.rs`;
+//! 2. apply the example's [overlay](crate::overlay), if it has one;
+//! 3. highlight the [conflicts](crate::conflict) of a `compile_fail` example;
+//! 4. write the timeline's printable copy with numbered
+//!    [callouts](crate::callouts).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::annotations::Annotations;
+use crate::build::SVG_FILES;
+use crate::callouts::{self, PRINT_FILE};
+use crate::conflict;
+use crate::layout::Layout;
+use crate::lines;
+use crate::manifest::Example;
+use crate::overlay::{Overlay, Stale, OVERLAY_FILE};
+use crate::rustc::Runner;
+
+/// An overlay entry that no longer applies, and the overlay it is in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StaleOverlay {
+    pub overlay: PathBuf,
+    pub stale: Stale,
+}
+
+/// Post-processes the SVGs of `example`, and of its modified copy if it has
+/// one. Examples without a visualization are left alone. Returns the overlay
+/// entries that did not match anything; the rest are still applied.
+pub fn postprocess(
+    layout: &Layout,
+    example: &Example,
+    runner: &mut Runner,
+) -> Result<Vec<StaleOverlay>> {
+    let mut stale = Vec::new();
+    if !example.visualization {
+        return Ok(stale);
+    }
+    for dir in example_dirs(layout, example) {
+        let annotations = Annotations::for_copy(layout, &example.name, &dir)?;
+        for file in SVG_FILES {
+            rewrite(&dir.join(file), |svg| {
+                let (svg, entries) = process(&dir, example, &annotations, runner, file, svg)?;
+                stale.extend(entries);
+                Ok(svg)
+            })?;
+        }
+        write_print_copy(&dir, &annotations)?;
+    }
+    Ok(stale)
+}
+
+/// Runs the steps on `text`, the contents of `file` (one of
+/// [`SVG_FILES`]) as the svg_generator wrote it for the copy of `example`
+/// in `dir`, whose events are `annotations`.
+pub fn process(
+    dir: &Path,
+    example: &Example,
+    annotations: &Annotations,
+    runner: &mut Runner,
+    file: &str,
+    text: &str,
+) -> Result<(String, Vec<StaleOverlay>)> {
+    let timeline = file == "vis_timeline.svg";
+    let mut text = text.to_owned();
+    let mut stale = Vec::new();
+    if timeline {
+        text = lines::annotate(&text, annotations)?;
+        if let Some(overlay) = Overlay::load(dir)? {
+            let (applied, entries) = overlay.apply(&text)?;
+            text = applied;
+            stale.extend(entries.into_iter().map(|stale| StaleOverlay {
+                overlay: dir.join(OVERLAY_FILE),
+                stale,
+            }));
+        }
+    }
+    if example.compile_fail.is_some() {
+        let path = dir.join("source.rs");
+        let source =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let conflicts = conflict::conflicts(runner.compile(&path)?, annotations, &source);
+        text = if timeline {
+            conflict::highlight_timeline(&text, &conflicts)?
+        } else {
+            conflict::highlight_code(&text, &conflicts)?
+        };
+    }
+    Ok((text, stale))
+}
+
+/// Writes [`PRINT_FILE`] from the post-processed timeline in `dir`, leaving
+/// it untouched if it is up to date.
+fn write_print_copy(dir: &Path, annotations: &Annotations) -> Result<()> {
+    let timeline = dir.join("vis_timeline.svg");
+    let text =
+        fs::read_to_string(&timeline).with_context(|| format!("reading {}", timeline.display()))?;
+    let (printed, _) = callouts::callouts(&text, annotations)
+        .with_context(|| format!("numbering the events of {}", timeline.display()))?;
+    let path = dir.join(PRINT_FILE);
+    if fs::read_to_string(&path).ok().as_deref() != Some(printed.as_str()) {
+        fs::write(&path, printed).with_context(|| format!("writing {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// The overlay entries of `example` that no longer match its SVGs, without
+/// changing anything.
+pub fn stale_overlays(layout: &Layout, example: &Example) -> Result<Vec<StaleOverlay>> {
+    let mut stale = Vec::new();
+    if !example.visualization {
+        return Ok(stale);
+    }
+    for dir in example_dirs(layout, example) {
+        let Some(overlay) = Overlay::load(&dir)? else {
+            continue;
+        };
+        let path = dir.join("vis_timeline.svg");
+        let text =
+            fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+        let (_, entries) = overlay.apply(&text)?;
+        stale.extend(entries.into_iter().map(|stale| StaleOverlay {
+            overlay: dir.join(OVERLAY_FILE),
+            stale,
+        }));
+    }
+    Ok(stale)
+}
+
+fn example_dirs(layout: &Layout, example: &Example) -> Vec<PathBuf> {
+    let mut dirs = vec![layout.example_dir(&example.name)];
+    if example.modified {
+        dirs.push(layout.modified_dir(&example.name));
+    }
+    dirs
+}
+
+/// Replaces the contents of `path` with `edit` applied to them, leaving the
+/// file untouched if nothing changed.
+fn rewrite(path: &Path, edit: impl FnOnce(&str) -> Result<String>) -> Result<()> {
+    let text = fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?;
+    let edited = edit(&text).with_context(|| format!("post-processing {}", path.display()))?;
+    if edited != text {
+        fs::write(path, edited).with_context(|| format!("writing {}", path.display()))?;
+    }
+    Ok(())
+}
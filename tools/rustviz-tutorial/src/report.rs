@@ -0,0 +1,125 @@
+//! Per-example results of a build, printed as a table or as JSON.
+
+use std::fmt;
+use std::io::{self, Write};
+
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    /// Both SVGs were regenerated and copied into the book.
+    Built,
+    /// A `main.rs` header was generated; its events have to be filled in by
+    /// hand before SVGs can be generated.
+    NeedsEvents,
+    /// The example is not listed in `examples.toml`.
+    NotInManifest,
+    /// `source.rs` is missing from the example directory.
+    MissingSource,
+    /// RustvizParse failed while generating the `main.rs` header.
+    HeaderFailed,
+    /// The svg_generator exited with an error.
+    SvgFailed,
+    /// The svg_generator succeeded but did not write both SVGs.
+    MissingSvg,
+    /// The SVGs were copied into the book but post-processing them failed.
+    PostprocessFailed,
+    /// The SVGs were built, but entries of the example's overlay no longer
+    /// match anything in them.
+    StaleOverlay,
+    /// Copying files between the book and the rustviz checkout failed.
+    Io,
+}
+
+impl Status {
+    pub fn is_ok(self) -> bool {
+        self == Status::Built
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Status::Built => "built",
+            Status::NeedsEvents => "needs events",
+            Status::NotInManifest => "not in manifest",
+            Status::MissingSource => "missing source.rs",
+            Status::HeaderFailed => "header failed",
+            Status::SvgFailed => "svg failed",
+            Status::MissingSvg => "missing svg",
+            Status::PostprocessFailed => "postprocess failed",
+            Status::StaleOverlay => "stale overlay",
+            Status::Io => "io error",
+        }
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExampleReport {
+    pub name: String,
+    pub status: Status,
+    /// Why the example is in this state, e.g. the tail of the generator's
+    /// stderr.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl ExampleReport {
+    pub fn new(name: &str, status: Status) -> Self {
+        ExampleReport {
+            name: name.to_owned(),
+            status,
+            detail: None,
+        }
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub examples: Vec<ExampleReport>,
+}
+
+impl Report {
+    pub fn push(&mut self, report: ExampleReport) {
+        self.examples.push(report);
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.examples.iter().all(|e| e.status.is_ok())
+    }
+
+    pub fn write_json(&self, out: &mut impl Write) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *out, self)?;
+        writeln!(out)
+    }
+
+    /// One line per example, followed by the indented detail if there is one.
+    pub fn write_table(&self, out: &mut impl Write) -> io::Result<()> {
+        let width = self
+            .examples
+            .iter()
+            .map(|e| e.name.len())
+            .max()
+            .unwrap_or(0);
+        for example in &self.examples {
+            writeln!(out, "{:width$}  {}", example.name, example.status)?;
+            if let Some(detail) = &example.detail {
+                for line in detail.lines() {
+                    writeln!(out, "    {line}")?;
+                }
+            }
+        }
+        let built = self.examples.iter().filter(|e| e.status.is_ok()).count();
+        writeln!(out, "\n{built} of {} examples built", self.examples.len())
+    }
+}
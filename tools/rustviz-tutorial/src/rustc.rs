@@ -0,0 +1,227 @@
+//! Compiling example sources with the local rustc.
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use tempfile::TempDir;
+
+/// Edition the examples are written against.
+pub const EDITION: &str = "2021";
+
+#[derive(Debug)]
+pub struct Compilation {
+    pub status: ExitStatus,
+    /// rustc's errors, in the order it reported them. Warnings are silenced.
+    pub diagnostics: Vec<Diagnostic>,
+    /// The human-readable diagnostics, as rustc would have printed them.
+    pub stderr: String,
+    /// Where the executable was written if compilation succeeded.
+    pub binary: PathBuf,
+}
+
+/// One message from rustc's `--error-format=json` output.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Diagnostic {
+    /// `error`, `failure-note`, ...
+    pub level: String,
+    #[serde(default, deserialize_with = "code")]
+    pub code: Option<String>,
+    /// The message on its own, e.g. "cannot borrow `x` as mutable more than
+    /// once at a time".
+    #[serde(default)]
+    pub message: String,
+    #[serde(default)]
+    pub spans: Vec<Span>,
+    /// The message as rustc renders it on a terminal, source snippet included.
+    #[serde(default)]
+    pub rendered: Option<String>,
+}
+
+/// A region of the source a diagnostic points at.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Span {
+    pub line_start: usize,
+    pub line_end: usize,
+    /// Whether this is what the message is about, as opposed to a label on
+    /// related code such as "first borrow later used here".
+    pub is_primary: bool,
+}
+
+impl Diagnostic {
+    /// The line of the primary span, where rustc puts the `^^^` marker.
+    pub fn primary_line(&self) -> Option<usize> {
+        self.spans
+            .iter()
+            .find(|span| span.is_primary)
+            .map(|span| span.line_start)
+    }
+
+    /// The first line rustc prints, e.g. `error[E0499]: cannot borrow ...`.
+    pub fn headline(&self) -> String {
+        match &self.code {
+            Some(code) => format!("{}[{code}]: {}", self.level, self.message),
+            None => format!("{}: {}", self.level, self.message),
+        }
+    }
+}
+
+impl Compilation {
+    pub fn success(&self) -> bool {
+        self.status.success()
+    }
+
+    /// Every distinct error code rustc reported, e.g. `E0505`.
+    pub fn error_codes(&self) -> Vec<&str> {
+        let mut codes: Vec<&str> = Vec::new();
+        for code in self.errors().filter_map(|d| d.code.as_deref()) {
+            if !codes.contains(&code) {
+                codes.push(code);
+            }
+        }
+        codes
+    }
+
+    /// Errors with an error code, leaving out the "aborting due to" summary
+    /// and the notes pointing at `rustc --explain`.
+    pub fn errors(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.level == "error" && d.code.is_some())
+    }
+}
+
+/// Compiles `source` as a binary crate into `binary`. Uses `$RUSTC` if set.
+///
+/// rustc runs in the directory of `source`, so diagnostics refer to the file
+/// as plain `source.rs` rather than by an absolute path.
+pub fn compile(source: &Path, binary: &Path) -> Result<Compilation> {
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| OsString::from("rustc"));
+    let dir = source
+        .parent()
+        .context("example source has no parent directory")?;
+    let file = source
+        .file_name()
+        .context("example source has no file name")?;
+    let binary = std::path::absolute(binary)?;
+    let output = Command::new(&rustc)
+        .args([
+            "--edition",
+            EDITION,
+            "--crate-type",
+            "bin",
+            "-A",
+            "warnings",
+        ])
+        .arg("--error-format=json")
+        .arg("-o")
+        .arg(&binary)
+        .arg(file)
+        .current_dir(dir)
+        .output()
+        .with_context(|| {
+            format!(
+                "running {} on {}",
+                rustc.to_string_lossy(),
+                source.display()
+            )
+        })?;
+
+    let mut diagnostics = Vec::new();
+    let mut stderr = String::new();
+    for line in String::from_utf8_lossy(&output.stderr).lines() {
+        match serde_json::from_str::<Diagnostic>(line) {
+            Ok(diagnostic) => {
+                stderr.push_str(diagnostic.rendered.as_deref().unwrap_or_default());
+                diagnostics.push(diagnostic);
+            }
+            // Not a diagnostic, e.g. a linker error or an ICE.
+            Err(_) => {
+                stderr.push_str(line);
+                stderr.push('\n');
+            }
+        }
+    }
+    Ok(Compilation {
+        status: output.status,
+        diagnostics,
+        stderr,
+        binary,
+    })
+}
+
+/// rustc writes the code as `{"code": "E0505", "explanation": "..."}`.
+fn code<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Code {
+        code: String,
+    }
+    Ok(Option::<Code>::deserialize(deserializer)?.map(|c| c.code))
+}
+
+/// Compiles and runs example sources, keeping the executables in a
+/// temporary directory and remembering the results.
+pub struct Runner {
+    dir: TempDir,
+    compilations: HashMap<PathBuf, Compilation>,
+    stdout: HashMap<PathBuf, String>,
+}
+
+impl Runner {
+    pub fn new() -> Result<Self> {
+        Ok(Runner {
+            dir: tempfile::tempdir().context("creating a directory for example binaries")?,
+            compilations: HashMap::new(),
+            stdout: HashMap::new(),
+        })
+    }
+
+    pub fn compile(&mut self, source: &Path) -> Result<&Compilation> {
+        if !self.compilations.contains_key(source) {
+            let binary = self
+                .dir
+                .path()
+                .join(format!("example-{}", self.compilations.len()));
+            let compilation = compile(source, &binary)?;
+            self.compilations.insert(source.to_owned(), compilation);
+        }
+        Ok(&self.compilations[source])
+    }
+
+    /// What `source` prints. Fails if it does not compile or exits with an
+    /// error.
+    pub fn stdout(&mut self, source: &Path) -> Result<&str> {
+        if !self.stdout.contains_key(source) {
+            let compilation = self.compile(source)?;
+            if !compilation.success() {
+                bail!(
+                    "{} does not compile:\n{}",
+                    source.display(),
+                    compilation.stderr
+                );
+            }
+            let binary = compilation.binary.clone();
+            let output = Command::new(&binary)
+                .output()
+                .with_context(|| format!("running {}", binary.display()))?;
+            if !output.status.success() {
+                bail!(
+                    "{} exited with {}:\n{}",
+                    source.display(),
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr)
+                );
+            }
+            let stdout = String::from_utf8(output.stdout)
+                .with_context(|| format!("{} printed invalid UTF-8", source.display()))?;
+            self.stdout.insert(source.to_owned(), stdout);
+        }
+        Ok(&self.stdout[source])
+    }
+}
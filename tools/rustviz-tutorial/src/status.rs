@@ -0,0 +1,282 @@
+//! How far the three copies of each example have drifted apart.
+//!
+//! An example lives in `src/assets/code_examples`, possibly with a
+//! hand-modified copy in `src/assets/modified_examples`, and in the rustviz
+//! checkout under `src/examples`, where its SVGs are generated. The book's
+//! SVGs are the generator's after [`postprocess`](crate::postprocess), so
+//! upstream SVGs are post-processed before being compared. Which side of a
+//! difference is newer comes from the history of each repository, not from
+//! file times, which every checkout and copy resets.
+
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+
+use crate::annotations::{Annotations, TRANSCRIPTION};
+use crate::build::SVG_FILES;
+use crate::layout::{Layout, Upstream};
+use crate::manifest::Example;
+use crate::postprocess;
+use crate::rustc::Runner;
+
+/// The book's copy of an example compared with the rustviz checkout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UpstreamStatus {
+    /// Every file matches.
+    InSync,
+    /// The files that differ were committed upstream more recently; copy
+    /// or rebuild them.
+    NewerUpstream,
+    /// The files that differ were committed to the book more recently, or
+    /// have uncommitted changes here.
+    LocalChanges,
+    /// Some differing files are newer on each side.
+    Diverged,
+    /// The checkout has no such example.
+    NotUpstream,
+}
+
+impl UpstreamStatus {
+    fn label(self) -> &'static str {
+        match self {
+            UpstreamStatus::InSync => "in sync",
+            UpstreamStatus::NewerUpstream => "newer upstream",
+            UpstreamStatus::LocalChanges => "local changes",
+            UpstreamStatus::Diverged => "diverged",
+            UpstreamStatus::NotUpstream => "not upstream",
+        }
+    }
+}
+
+impl fmt::Display for UpstreamStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+/// A modified example's `source.rs` compared with the one in
+/// `code_examples` it was made from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModifiedStatus {
+    MatchesBase,
+    SourceDiffers,
+    Missing,
+}
+
+impl ModifiedStatus {
+    fn label(self) -> &'static str {
+        match self {
+            ModifiedStatus::MatchesBase => "matches base",
+            ModifiedStatus::SourceDiffers => "source differs from base",
+            ModifiedStatus::Missing => "missing",
+        }
+    }
+}
+
+impl fmt::Display for ModifiedStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExampleStatus {
+    pub name: String,
+    /// `None` without a rustviz checkout.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<UpstreamStatus>,
+    /// The files that differ from upstream, e.g. `source.rs`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub differing: Vec<String>,
+    /// `None` for examples without a modified copy.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modified: Option<ModifiedStatus>,
+}
+
+impl ExampleStatus {
+    /// Whether nothing needs attention.
+    pub fn is_ok(&self) -> bool {
+        matches!(self.upstream, None | Some(UpstreamStatus::InSync))
+            && matches!(self.modified, None | Some(ModifiedStatus::MatchesBase))
+    }
+}
+
+/// Compares the copies of `example`. Pass `None` for `upstream` if there is
+/// no rustviz checkout.
+pub fn status(
+    layout: &Layout,
+    upstream: Option<&Upstream>,
+    example: &Example,
+    runner: &mut Runner,
+) -> Result<ExampleStatus> {
+    let local = layout.example_dir(&example.name);
+    let mut status = ExampleStatus {
+        name: example.name.clone(),
+        upstream: None,
+        differing: Vec::new(),
+        modified: None,
+    };
+
+    if let Some(upstream) = upstream {
+        let remote = upstream.example_dir(&example.name);
+        if !remote.join("source.rs").is_file() {
+            status.upstream = Some(UpstreamStatus::NotUpstream);
+        } else {
+            let mut files = vec!["source.rs"];
+            if example.visualization {
+                files.extend(SVG_FILES);
+            }
+            // Upstream's SVGs were drawn from its own `main.rs`, unless the
+            // book has one to send it.
+            let events = if local.join("main.rs").is_file() || !remote.join("main.rs").is_file() {
+                &local
+            } else {
+                &remote
+            };
+            let (mut newer_upstream, mut newer_local) = (false, false);
+            for file in files {
+                let Some(theirs) = read(&remote.join(file))? else {
+                    continue;
+                };
+                let theirs = if file == "source.rs" {
+                    theirs
+                } else {
+                    let annotations = Annotations::load(events)?;
+                    postprocess::process(&local, example, &annotations, runner, file, &theirs)
+                        .with_context(|| {
+                            format!("post-processing {}", remote.join(file).display())
+                        })?
+                        .0
+                };
+                if read(&local.join(file))?.as_deref() == Some(theirs.as_str()) {
+                    continue;
+                }
+                status.differing.push(file.to_owned());
+                if last_changed(&remote.join(file))? > last_changed(&local.join(file))? {
+                    newer_upstream = true;
+                } else {
+                    newer_local = true;
+                }
+            }
+            if example.visualization
+                && events == &remote
+                && local.join(TRANSCRIPTION).is_file()
+                && Annotations::load(&local)?.events != Annotations::load(&remote)?.events
+            {
+                status.differing.push(TRANSCRIPTION.to_owned());
+                newer_upstream = true;
+            }
+            status.upstream = Some(match (newer_upstream, newer_local) {
+                (false, false) => UpstreamStatus::InSync,
+                (true, false) => UpstreamStatus::NewerUpstream,
+                (false, true) => UpstreamStatus::LocalChanges,
+                (true, true) => UpstreamStatus::Diverged,
+            });
+        }
+    }
+
+    if example.modified {
+        let base = read(&local.join("source.rs"))?;
+        status.modified = Some(
+            match read(&layout.modified_dir(&example.name).join("source.rs"))? {
+                None => ModifiedStatus::Missing,
+                Some(source) if Some(&source) == base.as_ref() => ModifiedStatus::MatchesBase,
+                Some(_) => ModifiedStatus::SourceDiffers,
+            },
+        );
+    }
+
+    Ok(status)
+}
+
+pub fn write_json(statuses: &[ExampleStatus], out: &mut impl Write) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, statuses)?;
+    writeln!(out)
+}
+
+/// One line per example that needs attention, then a summary.
+pub fn write_table(statuses: &[ExampleStatus], out: &mut impl Write) -> io::Result<()> {
+    let width = statuses.iter().map(|s| s.name.len()).max().unwrap_or(0);
+    for status in statuses.iter().filter(|s| !s.is_ok()) {
+        let mut parts = Vec::new();
+        match status.upstream {
+            Some(UpstreamStatus::InSync) | None => {}
+            Some(upstream) if status.differing.is_empty() => parts.push(upstream.to_string()),
+            Some(upstream) => parts.push(format!("{upstream} ({})", status.differing.join(", "))),
+        }
+        if let Some(modified) = status
+            .modified
+            .filter(|m| *m != ModifiedStatus::MatchesBase)
+        {
+            parts.push(format!("modified copy: {modified}"));
+        }
+        writeln!(out, "{:width$}  {}", status.name, parts.join("; "))?;
+    }
+    let ok = statuses.iter().filter(|s| s.is_ok()).count();
+    writeln!(out, "\n{ok} of {} examples up to date", statuses.len())
+}
+
+fn read(path: &Path) -> Result<Option<String>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    fs::read_to_string(path)
+        .map(Some)
+        .with_context(|| format!("reading {}", path.display()))
+}
+
+/// When `path` last changed in the git repository it is in: the time of its
+/// last commit, or later than any commit if it has uncommitted changes.
+/// `None` if it does not exist, so a missing copy is always the older one.
+fn last_changed(path: &Path) -> Result<Option<Changed>> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let dir = path
+        .parent()
+        .context("example file has no parent directory")?;
+    let file = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .context("example file name is not UTF-8")?;
+    if !git(dir, &["status", "--porcelain", "--", file])?.is_empty() {
+        return Ok(Some(Changed::Uncommitted));
+    }
+    let time = git(dir, &["log", "-1", "--format=%ct", "--", file])?;
+    Ok(Some(match time.parse() {
+        Ok(time) => Changed::Committed(time),
+        Err(_) => Changed::Uncommitted,
+    }))
+}
+
+/// When a file last changed; later is greater.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Changed {
+    Committed(i64),
+    Uncommitted,
+}
+
+/// The trimmed output of a successful git command run in `dir`.
+fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .context("running git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} in {} failed: {}",
+            args.join(" "),
+            dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
@@ -0,0 +1,68 @@
+//! The order `helpers.js` plays a visualization back in.
+//!
+//! Each step is one element of a `vis_timeline.svg` that draws an event: a
+//! dot, an arrow or a function call. Steps follow the events in `main.rs`,
+//! in the [same order](crate::event_order) as the event table and the
+//! printed callouts. The `rustviz` preprocessor writes them into the chapter
+//! as JSON, so the browser never has to work the order out from the drawing.
+
+use anyhow::{Context, Result};
+use roxmltree::{Document, Node};
+use serde::Serialize;
+
+use crate::annotations::Annotations;
+use crate::event_order;
+use crate::svg;
+use crate::timeline::{Element, Kind, Timeline};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Step {
+    /// The source line to highlight in the code panel.
+    pub line: usize,
+    pub variable: Option<String>,
+    pub kind: Kind,
+    /// The event's tooltip as plain text.
+    pub description: String,
+    /// The timeline elements to emphasize: the event itself, then the states
+    /// of its variable that cover its line. Each is an index into the
+    /// timeline's `.tooltip-trigger` elements in document order.
+    pub elements: Vec<usize>,
+}
+
+/// The steps through `vis_timeline.svg`, one for every element that draws
+/// one of the events in `annotations`.
+pub fn steps(text: &str, annotations: &Annotations) -> Result<Vec<Step>> {
+    let doc = Document::parse(text).context("parsing timeline SVG")?;
+    let triggers: Vec<Node> = doc
+        .descendants()
+        .filter(|n| svg::has_class(*n, "tooltip-trigger"))
+        .collect();
+    let index = |element: &Element| {
+        triggers
+            .iter()
+            .position(|t| *t == element.node)
+            .expect("every element is a tooltip trigger")
+    };
+    let timeline = Timeline::new(&doc);
+    Ok(event_order::order(&timeline, annotations)?
+        .into_iter()
+        .filter_map(|ordered| {
+            let line = annotations.events[ordered.event?].line;
+            let event = ordered.element;
+            let states = timeline.elements.iter().filter(|e| {
+                matches!(
+                    e.kind,
+                    Kind::Timeline | Kind::StaticRefLine | Kind::MutRefLine
+                ) && e.column == event.column
+                    && e.covers(line)
+            });
+            Some(Step {
+                line,
+                variable: timeline.variable(event).map(str::to_owned),
+                kind: event.kind,
+                description: svg::plain_text(event.tooltip()),
+                elements: [event].into_iter().chain(states).map(index).collect(),
+            })
+        })
+        .collect())
+}
@@ -0,0 +1,226 @@
+//! Reading and editing the SVGs the svg_generator writes.
+//!
+//! Edits are spliced into the original text instead of writing out a parsed
+//! tree, so everything they do not touch stays byte for byte as the
+//! generator left it and applying them to their own output can be a no-op.
+
+use std::ops::Range;
+
+use roxmltree::Node;
+
+/// The `<span>` the svg_generator wraps variable and function names in
+/// inside tooltips.
+pub fn code_span(name: &str) -> String {
+    format!(
+        "<span style=\"font-family: 'Source Code Pro', Consolas, 'Ubuntu Mono', Menlo, \
+         'DejaVu Sans Mono', monospace, monospace !important;\">{name}</span>"
+    )
+}
+
+/// Tooltip HTML for plain text, with names in backticks set in the code
+/// font like the svg_generator's own tooltips.
+pub fn tooltip_html(text: &str) -> String {
+    let mut out = String::new();
+    for (i, part) in text.split('`').enumerate() {
+        if i % 2 == 1 {
+            out.push_str(&code_span(&escape(part)));
+        } else {
+            out.push_str(&escape(part));
+        }
+    }
+    out
+}
+
+/// The text of tooltip HTML with the tags removed and whitespace collapsed,
+/// e.g. "x acquires ownership of a resource".
+pub fn plain_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = rest[start..].split_once('>').map_or("", |(_, after)| after);
+    }
+    text.push_str(rest);
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// The names tooltip HTML sets in the code font with [`code_span`], in
+/// order.
+pub fn code_names(html: &str) -> Vec<String> {
+    html.split("<span")
+        .skip(1)
+        .filter_map(|part| part.split_once('>')?.1.split_once("</span>"))
+        .map(|(name, _)| plain_text(name))
+        .collect()
+}
+
+/// The lowest and highest y a path's data `d` reaches, for paths of
+/// straight lines: `M`, `L`, `H`, `V` and `Z`, absolute or relative.
+/// `None` for anything else.
+pub fn vertical_extent(d: &str) -> Option<(f64, f64)> {
+    let mut tokens = Vec::new();
+    let mut number = String::new();
+    for c in d.chars() {
+        if c.is_ascii_digit() || c == '.' || (c == '-' && number.is_empty()) {
+            number.push(c);
+            continue;
+        }
+        if !number.is_empty() {
+            tokens.push(PathToken::Number(number.parse().ok()?));
+            number.clear();
+        }
+        if c.is_ascii_alphabetic() {
+            tokens.push(PathToken::Command(c));
+        } else if !(c.is_whitespace() || c == ',') {
+            return None;
+        }
+    }
+    if !number.is_empty() {
+        tokens.push(PathToken::Number(number.parse().ok()?));
+    }
+
+    let (mut x, mut y, mut start) = (0.0, 0.0, (0.0, 0.0));
+    let (mut low, mut high) = (f64::INFINITY, f64::NEG_INFINITY);
+    let mut command = None;
+    let mut tokens = tokens.into_iter().peekable();
+    while tokens.peek().is_some() {
+        if let Some(PathToken::Command(c)) = tokens.peek() {
+            command = Some(*c);
+            tokens.next();
+        }
+        let mut next = || match tokens.next() {
+            Some(PathToken::Number(n)) => Some(n),
+            _ => None,
+        };
+        let relative = command?.is_ascii_lowercase();
+        let (dx, dy) = if relative { (x, y) } else { (0.0, 0.0) };
+        match command?.to_ascii_uppercase() {
+            'M' | 'L' => {
+                (x, y) = (dx + next()?, dy + next()?);
+                if command?.eq_ignore_ascii_case(&'M') {
+                    start = (x, y);
+                    // Coordinates after a moveto are linetos.
+                    command = Some(if relative { 'l' } else { 'L' });
+                }
+            }
+            'H' => x = dx + next()?,
+            'V' => y = dy + next()?,
+            'Z' => {
+                (x, y) = start;
+                command = None;
+            }
+            _ => return None,
+        }
+        low = low.min(y);
+        high = high.max(y);
+    }
+    low.is_finite().then_some((low, high))
+}
+
+enum PathToken {
+    Command(char),
+    Number(f64),
+}
+
+/// Escapes text for use in an attribute value or element content.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+pub fn has_class(node: Node, class: &str) -> bool {
+    node.attribute("class")
+        .is_some_and(|classes| classes.split_whitespace().any(|c| c == class))
+}
+
+/// The `<g>` with the given id, e.g. `events` or `code`.
+pub fn group<'a, 'input>(
+    doc: &'a roxmltree::Document<'input>,
+    id: &str,
+) -> Option<Node<'a, 'input>> {
+    doc.descendants()
+        .find(|n| n.has_tag_name("g") && n.attribute("id") == Some(id))
+}
+
+/// Text edits against one document, applied all at once.
+#[derive(Debug, Default)]
+pub struct Edits {
+    edits: Vec<(Range<usize>, String)>,
+}
+
+impl Edits {
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    pub fn replace(&mut self, range: Range<usize>, text: impl Into<String>) {
+        self.edits.push((range, text.into()));
+    }
+
+    pub fn insert(&mut self, at: usize, text: impl Into<String>) {
+        self.replace(at..at, text);
+    }
+
+    /// Appends `class` to the element's class attribute, adding the
+    /// attribute if it has none.
+    pub fn add_class(&mut self, node: Node, class: &str) {
+        match node.attribute_node("class") {
+            Some(attr) => self.insert(attr.range_value().end, format!(" {class}")),
+            None => self.insert(start_tag_name_end(node), format!(" class=\"{class}\"")),
+        }
+    }
+
+    /// Sets an attribute of the element, replacing its value if it has one.
+    /// Nothing changes if it already has `value`.
+    pub fn set_attribute(&mut self, node: Node, name: &str, value: &str) {
+        match node.attribute_node(name) {
+            Some(attr) if attr.value() == value => {}
+            Some(attr) => self.replace(attr.range_value(), escape(value)),
+            None => self.insert(
+                attributes_end(node),
+                format!(" {name}=\"{}\"", escape(value)),
+            ),
+        }
+    }
+
+    /// Applies the edits to `text`, the document they were made against.
+    /// Insertions at the same position keep the order they were made in.
+    pub fn apply(mut self, text: &str) -> String {
+        self.edits.sort_by_key(|(range, _)| range.start);
+        let mut out = String::with_capacity(text.len());
+        let mut pos = 0;
+        for (range, replacement) in self.edits {
+            out.push_str(&text[pos..range.start]);
+            out.push_str(&replacement);
+            pos = range.end;
+        }
+        out.push_str(&text[pos..]);
+        out
+    }
+}
+
+/// Just past `<tag` in the element's start tag.
+fn start_tag_name_end(node: Node) -> usize {
+    node.range().start + 1 + node.tag_name().name().len()
+}
+
+/// Just past the last attribute in the element's start tag.
+fn attributes_end(node: Node) -> usize {
+    node.attributes()
+        .next_back()
+        .map_or_else(|| start_tag_name_end(node), |attr| attr.range().end)
+}
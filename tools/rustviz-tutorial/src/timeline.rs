@@ -0,0 +1,144 @@
+//! The interactive elements of a `vis_timeline.svg`.
+//!
+//! The svg_generator lays every variable out as a column headed by its label
+//! and tags what it draws on the column with the label's `data-hash`.
+//! [`lines`](crate::lines) writes the source lines of every element onto it
+//! from the example's event annotations; this reads both back.
+
+use roxmltree::{Document, Node};
+pub use rustviz_events::Kind;
+
+use crate::lines::{LINE_END, LINE_START};
+use crate::svg;
+
+/// A variable's column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub x: f64,
+    pub hash: Option<String>,
+}
+
+/// One tooltip trigger.
+#[derive(Debug, Clone, Copy)]
+pub struct Element<'a, 'input> {
+    pub node: Node<'a, 'input>,
+    pub kind: Kind,
+    /// The first and last source line the element covers. Variable labels
+    /// are not tied to a line.
+    pub lines: Option<(usize, usize)>,
+    /// Index into [`Timeline::columns`] of the column the element is drawn
+    /// on. For arrows this is the column the arrow points to.
+    pub column: Option<usize>,
+}
+
+impl Element<'_, '_> {
+    /// The raw HTML of the tooltip.
+    pub fn tooltip(&self) -> &str {
+        self.node.attribute("data-tooltip-text").unwrap_or_default()
+    }
+
+    /// Whether the element covers `line`.
+    pub fn covers(&self, line: usize) -> bool {
+        self.lines
+            .is_some_and(|(start, end)| (start..=end).contains(&line))
+    }
+}
+
+/// The columns and tooltip triggers of a parsed `vis_timeline.svg`.
+pub struct Timeline<'a, 'input> {
+    pub columns: Vec<Column>,
+    pub elements: Vec<Element<'a, 'input>>,
+}
+
+impl<'a, 'input> Timeline<'a, 'input> {
+    pub fn new(doc: &'a Document<'input>) -> Self {
+        let columns = columns(doc);
+        let elements = doc
+            .descendants()
+            .filter(|n| svg::has_class(*n, "tooltip-trigger"))
+            .filter_map(|node| {
+                let kind = kind(node)?;
+                let lines = lines(node);
+                let column = column(&columns, node, kind);
+                Some(Element {
+                    node,
+                    kind,
+                    lines,
+                    column,
+                })
+            })
+            .collect();
+        Timeline { columns, elements }
+    }
+
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.name == name)
+    }
+
+    /// The name of the variable an element is drawn on.
+    pub fn variable(&self, element: &Element) -> Option<&str> {
+        element.column.map(|i| self.columns[i].name.as_str())
+    }
+}
+
+/// Where the svg_generator puts the events of `line` when every line up to
+/// it has some, for drawing new elements. Lines are read from the
+/// attributes, never worked out from this.
+pub fn event_y(line: usize) -> usize {
+    30 * line + 55
+}
+
+/// The kind `helpers.js` would log for an element, from its tag and
+/// first class.
+fn kind(node: Node) -> Option<Kind> {
+    let class = node.attribute("class")?.split_whitespace().next();
+    Some(match (node.tag_name().name(), class) {
+        ("text", Some("label" | "functionLogo")) => Kind::Label,
+        ("path", Some("hollow")) | ("line", _) => Kind::Timeline,
+        ("path", Some("staticref")) => Kind::StaticRefLine,
+        ("path", Some("mutref")) => Kind::MutRefLine,
+        ("circle", _) => Kind::Event,
+        ("use", _) => Kind::FunctionEvent,
+        ("polyline", _) => Kind::Arrow,
+        ("rect", _) => Kind::StructBox,
+        _ => return None,
+    })
+}
+
+/// The variable labels, in column order. A reference's label reads
+/// `y|*y`; its name is the text before the `|`.
+fn columns(doc: &Document) -> Vec<Column> {
+    doc.descendants()
+        .filter(|n| n.has_tag_name("text") && svg::has_class(*n, "label"))
+        .filter_map(|node| {
+            let name = node.first_child()?.text()?.trim().to_owned();
+            let x = node.attribute("x")?.parse().ok()?;
+            let hash = node.attribute("data-hash").map(str::to_owned);
+            Some(Column { name, x, hash })
+        })
+        .collect()
+}
+
+/// The lines [`lines`](crate::lines) wrote onto an element.
+fn lines(node: Node) -> Option<(usize, usize)> {
+    let line = |name| node.attribute(name)?.parse().ok();
+    Some((line(LINE_START)?, line(LINE_END)?))
+}
+
+/// The column an element is drawn on: the one whose label shares its
+/// `data-hash`. Arrows have none; they belong to the variable they point
+/// to, named last in their tooltip, or the one they leave for a function.
+fn column(columns: &[Column], node: Node, kind: Kind) -> Option<usize> {
+    match kind {
+        Kind::Label if svg::has_class(node, "label") => None,
+        Kind::Arrow => svg::code_names(node.attribute("data-tooltip-text")?)
+            .iter()
+            .rev()
+            .find_map(|name| columns.iter().position(|c| &c.name == name)),
+        _ => {
+            let hash = node.attribute("data-hash")?;
+            columns.iter().position(|c| c.hash.as_deref() == Some(hash))
+        }
+    }
+}
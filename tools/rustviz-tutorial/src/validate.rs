@@ -0,0 +1,239 @@
+//! Consistency checks between `examples.toml`, the example directories and
+//! the chapters that show them.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::annotations::Annotations;
+use crate::build::SVG_FILES;
+use crate::callouts::PRINT_FILE;
+use crate::chapters::{self, Reference};
+use crate::layout::Layout;
+use crate::manifest::Manifest;
+use crate::overlay::Stale;
+use crate::postprocess::stale_overlays;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// An example directory that is not listed in the manifest.
+    Orphan { dir: PathBuf },
+    /// The same name is listed more than once in the manifest.
+    Duplicate { name: String },
+    /// A manifest entry whose directory does not exist.
+    MissingDirectory { name: String, dir: PathBuf },
+    /// A required file is missing from an example directory.
+    MissingFile { name: String, file: PathBuf },
+    /// A chapter refers to an example that is not in the manifest.
+    UnknownExample { chapter: String, name: String },
+    /// A chapter uses an example that the manifest assigns elsewhere.
+    WrongChapter {
+        chapter: String,
+        name: String,
+        expected: Option<String>,
+    },
+    /// The manifest names a chapter that never refers to the example.
+    Unreferenced { name: String, chapter: String },
+    /// The manifest names a chapter file that does not exist.
+    MissingChapter { name: String, chapter: String },
+    /// An entry of an example's overlay no longer matches its SVG.
+    StaleOverlay { overlay: PathBuf, stale: Stale },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Orphan { dir } => {
+                write!(f, "{} is not listed in examples.toml", dir.display())
+            }
+            Problem::Duplicate { name } => {
+                write!(f, "{name} is listed more than once in examples.toml")
+            }
+            Problem::MissingDirectory { name, dir } => {
+                write!(f, "{name}: {} does not exist", dir.display())
+            }
+            Problem::MissingFile { name, file } => {
+                write!(f, "{name}: {} is missing", file.display())
+            }
+            Problem::UnknownExample { chapter, name } => {
+                write!(f, "{chapter} refers to {name}, which is not an example")
+            }
+            Problem::WrongChapter {
+                chapter,
+                name,
+                expected: Some(expected),
+            } => write!(
+                f,
+                "{chapter} refers to {name}, but examples.toml assigns it to {expected}"
+            ),
+            Problem::WrongChapter {
+                chapter,
+                name,
+                expected: None,
+            } => write!(
+                f,
+                "{chapter} refers to {name}, but examples.toml gives it no chapter"
+            ),
+            Problem::Unreferenced { name, chapter } => {
+                write!(f, "{name}: {chapter} does not refer to it")
+            }
+            Problem::MissingChapter { name, chapter } => {
+                write!(f, "{name}: chapter {chapter} does not exist")
+            }
+            Problem::StaleOverlay { overlay, stale } => {
+                write!(f, "{}: {stale}", overlay.display())
+            }
+        }
+    }
+}
+
+/// Checks the manifest against the tree and returns every problem found.
+pub fn validate(layout: &Layout, manifest: &Manifest) -> Result<Vec<Problem>> {
+    let mut problems = Vec::new();
+
+    let mut names = BTreeSet::new();
+    for example in &manifest.examples {
+        if !names.insert(example.name.as_str()) {
+            problems.push(Problem::Duplicate {
+                name: example.name.clone(),
+            });
+        }
+    }
+
+    let modified: BTreeSet<&str> = manifest
+        .examples
+        .iter()
+        .filter(|e| e.modified)
+        .map(|e| e.name.as_str())
+        .collect();
+    for dir in subdirectories(&layout.code_examples())? {
+        if !names.contains(dir_name(&dir)) {
+            problems.push(Problem::Orphan { dir });
+        }
+    }
+    for dir in subdirectories(&layout.modified_examples())? {
+        if !modified.contains(dir_name(&dir)) {
+            problems.push(Problem::Orphan { dir });
+        }
+    }
+
+    for example in &manifest.examples {
+        let mut dirs = vec![layout.example_dir(&example.name)];
+        if example.modified {
+            dirs.push(layout.modified_dir(&example.name));
+        }
+        let mut files = vec!["source.rs"];
+        if example.visualization {
+            files.extend(SVG_FILES);
+            files.push(PRINT_FILE);
+        }
+        let found = problems.len();
+        // Timelines take their lines from the events in the base example's
+        // `main.rs` or transcription; a modified copy may bring its own.
+        if example.visualization && dirs[0].is_dir() && Annotations::path(&dirs[0]).is_none() {
+            problems.push(Problem::MissingFile {
+                name: example.name.clone(),
+                file: dirs[0].join("main.rs"),
+            });
+        }
+        for dir in dirs {
+            if !dir.is_dir() {
+                problems.push(Problem::MissingDirectory {
+                    name: example.name.clone(),
+                    dir,
+                });
+                continue;
+            }
+            for file in &files {
+                let file = dir.join(file);
+                if !file.is_file() {
+                    problems.push(Problem::MissingFile {
+                        name: example.name.clone(),
+                        file,
+                    });
+                }
+            }
+        }
+        // Overlays can only be checked against SVGs that are there.
+        if problems.len() == found {
+            for stale in stale_overlays(layout, example)? {
+                problems.push(Problem::StaleOverlay {
+                    overlay: stale.overlay,
+                    stale: stale.stale,
+                });
+            }
+        }
+    }
+
+    let chapters = chapters::load(&layout.src())?;
+    let mut referenced: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for chapter in &chapters {
+        for Reference { name, modified } in chapter.references()? {
+            let Some(example) = manifest.get(&name) else {
+                problems.push(Problem::UnknownExample {
+                    chapter: chapter.file.clone(),
+                    name,
+                });
+                continue;
+            };
+            if modified && !example.modified {
+                problems.push(Problem::UnknownExample {
+                    chapter: chapter.file.clone(),
+                    name: format!("modified_examples/{name}"),
+                });
+            }
+            if example.chapter.as_deref() != Some(chapter.file.as_str()) {
+                problems.push(Problem::WrongChapter {
+                    chapter: chapter.file.clone(),
+                    name,
+                    expected: example.chapter.clone(),
+                });
+            }
+            referenced
+                .entry(chapter.file.as_str())
+                .or_default()
+                .insert(example.name.as_str());
+        }
+    }
+
+    for example in &manifest.examples {
+        let Some(chapter) = &example.chapter else {
+            continue;
+        };
+        if !chapters.iter().any(|c| &c.file == chapter) {
+            problems.push(Problem::MissingChapter {
+                name: example.name.clone(),
+                chapter: chapter.clone(),
+            });
+        } else if !referenced
+            .get(chapter.as_str())
+            .is_some_and(|names| names.contains(example.name.as_str()))
+        {
+            problems.push(Problem::Unreferenced {
+                name: example.name.clone(),
+                chapter: chapter.clone(),
+            });
+        }
+    }
+
+    Ok(problems)
+}
+
+fn subdirectories(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_dir() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+fn dir_name(dir: &Path) -> &str {
+    dir.file_name().and_then(|n| n.to_str()).unwrap_or_default()
+}
@@ -0,0 +1,151 @@
+//! Bundles a scratch book and the real one for offline use and checks what
+//! the bundle loads.
+
+use std::ffi::OsString;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use rustviz_tutorial::bundle::bundle;
+use rustviz_tutorial::Layout;
+use tempfile::TempDir;
+
+/// A built book with one chapter showing the `copy` example, a font
+/// stylesheet, a script that talks to the playground when online, and
+/// Google Analytics turned on.
+fn scratch_book() -> TempDir {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let book = TempDir::new().unwrap();
+    let example = book.path().join("assets/code_examples/copy");
+    fs::create_dir_all(&example).unwrap();
+    for file in ["vis_code.svg", "vis_timeline.svg"] {
+        fs::copy(layout.example_dir("copy").join(file), example.join(file)).unwrap();
+    }
+    fs::create_dir_all(book.path().join("fonts")).unwrap();
+    fs::write(book.path().join("fonts/code.woff2"), b"font").unwrap();
+    fs::write(
+        book.path().join("fonts/fonts.css"),
+        "@font-face {\n  font-family: 'Source Code Pro';\n  src: local('Source Code Pro'),\n       \
+         url('../fonts/code.woff2') format('woff2'), url('../fonts/code.woff') format('woff');\n}\n",
+    )
+    .unwrap();
+    fs::write(
+        book.path().join("book.js"),
+        "const SVG_NS = \"http://www.w3.org/2000/svg\";\n\
+         // online only {\n\
+         fetch(\"https://play.rust-lang.org/meta/crates\");\n\
+         // } online only\n\
+         // see https://play.rust-lang.org\n",
+    )
+    .unwrap();
+    fs::write(book.path().join("index.html"), "<p>RustViz</p>").unwrap();
+    fs::write(
+        book.path().join("ownership.html"),
+        r#"<link rel="stylesheet" href="fonts/fonts.css">
+<a href="https://doc.rust-lang.org/book/">The Book</a>
+<div class="flex-container vis_block">
+  <object type="image/svg+xml" class="copy code_panel" data="assets/code_examples/copy/vis_code.svg"></object>
+  <object type="image/svg+xml" class="copy tl_panel" data="assets/code_examples/copy/vis_timeline.svg" style="width: auto;" onmouseenter="helpers('copy')"></object>
+</div>
+<div id="rustviz-telemetry" data-analytics="google" hidden></div>"#,
+    )
+    .unwrap();
+    book
+}
+
+#[test]
+fn inlines_svgs_embeds_fonts_and_turns_telemetry_off() {
+    let book = scratch_book();
+    let dir = TempDir::new().unwrap();
+    let out = dir.path().join("bundle");
+    let report = bundle(book.path(), &out).unwrap();
+    assert_eq!(
+        (report.pages, report.svgs, report.fonts, report.scripts),
+        (2, 2, 1, 1)
+    );
+
+    let page = fs::read_to_string(out.join("ownership.html")).unwrap();
+    assert!(!page.contains("<object"));
+    assert!(page.contains(
+        r#"<iframe class="copy tl_panel" onmouseenter="helpers('copy')" style="border: 0; width: 240px; height: 280px;" srcdoc="&lt;!DOCTYPE html&gt;"#
+    ));
+    assert!(page.contains("&lt;desc&gt;examples/copy/input/&lt;/desc&gt;"));
+    assert!(page.contains(r#"<div id="rustviz-telemetry" data-analytics="off" hidden>"#));
+    assert!(page.contains(r#"<a href="https://doc.rust-lang.org/book/">"#));
+
+    let fonts = fs::read_to_string(out.join("fonts/fonts.css")).unwrap();
+    assert!(fonts.contains(
+        "src: local('Source Code Pro'), url(data:font/woff2;base64,Zm9udA==) format('woff2');"
+    ));
+
+    assert_eq!(
+        fs::read_to_string(out.join("book.js")).unwrap(),
+        "const SVG_NS = \"http://www.w3.org/2000/svg\";\n// see https://play.rust-lang.org\n"
+    );
+}
+
+#[test]
+fn fails_on_what_only_the_network_has() {
+    let book = scratch_book();
+    fs::write(
+        book.path().join("analytics.html"),
+        r#"<script async src="https://www.googletagmanager.com/gtag/js"></script>"#,
+    )
+    .unwrap();
+    let dir = TempDir::new().unwrap();
+    let out = dir.path().join("bundle");
+    let err = bundle(book.path(), &out).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "analytics.html loads https://www.googletagmanager.com/gtag/js"
+    );
+    assert!(!out.exists());
+
+    let book = scratch_book();
+    fs::write(
+        book.path().join("playground.js"),
+        "fetch('https://play.rust-lang.org/evaluate.json', { method: 'POST' });\n",
+    )
+    .unwrap();
+    let err = bundle(book.path(), &out).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "playground.js loads https://play.rust-lang.org/evaluate.json"
+    );
+    assert!(!out.exists());
+}
+
+/// The book as `mdbook build` writes it, with the theme's `book.js` and
+/// Google Analytics on, bundles without anything left that needs the
+/// network. Uses `$MDBOOK` if set.
+#[test]
+fn bundles_the_built_book() {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let dir = TempDir::new().unwrap();
+    let book = dir.path().join("book");
+    let mdbook = std::env::var_os("MDBOOK").unwrap_or_else(|| OsString::from("mdbook"));
+    let output = Command::new(&mdbook)
+        .arg("build")
+        .arg("--dest-dir")
+        .arg(&book)
+        .current_dir(layout.root())
+        .env("MDBOOK_PREPROCESSOR__RUSTVIZ__ANALYTICS", "google")
+        .output()
+        .unwrap_or_else(|err| panic!("running {}: {err}", mdbook.to_string_lossy()));
+    assert!(
+        output.status.success(),
+        "mdbook build failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let built = fs::read_to_string(book.join("book.js")).unwrap();
+    assert!(built.contains("https://play.rust-lang.org/"));
+    assert!(built.contains("https://www.googletagmanager.com/"));
+
+    let out = dir.path().join("bundle");
+    let report = bundle(&book, &out).unwrap();
+    assert!(report.svgs > 0);
+    assert_eq!(report.scripts, 1);
+    let offline = fs::read_to_string(out.join("book.js")).unwrap();
+    assert!(!offline.contains("https://play.rust-lang.org/"));
+    assert!(!offline.contains("https://www.googletagmanager.com/"));
+}
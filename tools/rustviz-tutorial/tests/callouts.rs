@@ -0,0 +1,101 @@
+//! Numbers the events of a timeline for print, and checks every printable
+//! copy is up to date.
+
+use std::fs;
+use std::path::Path;
+
+use roxmltree::Document;
+use rustviz_tutorial::annotations::Annotations;
+use rustviz_tutorial::callouts::{callouts, PRINT_FILE};
+use rustviz_tutorial::timeline::Kind;
+use rustviz_tutorial::{Layout, Manifest};
+
+const MAIN_RS: &str = "/* --- BEGIN Variable Definitions ---
+Owner x;
+Owner y;
+--- END Variable Definitions --- */
+fn main() {
+    let x = y; // !{ Move(y->x) }
+    println!(\"{}\", x);
+} /* !{ GoOutOfScope(x) } */
+";
+
+#[test]
+fn numbers_events_in_event_order() {
+    let timeline = r#"<svg width="240px" height="280px" xmlns="http://www.w3.org/2000/svg">
+        <text x="70" y="70" class="label tooltip-trigger" data-hash="1" data-tooltip-text="x, immutable">x</text>
+        <path class="hollow tooltip-trigger" data-hash="1" d="M 68.2,100 V 210 h 3.5 V 100 h -3.5" data-line-start="2" data-line-end="4" data-tooltip-text="x is the owner"/>
+        <circle cx="70" cy="210" r="5" data-hash="1" class="tooltip-trigger" data-line-start="4" data-line-end="4" data-tooltip-text="x goes out of scope"/>
+        <circle cx="70" cy="100" r="5" data-hash="1" class="tooltip-trigger" data-line-start="2" data-line-end="2" data-tooltip-text="x is initialized"/>
+        <polyline points="130,100 80,100" class="tooltip-trigger" data-tooltip-text="Move from &lt;span&gt;y&lt;/span&gt; to &lt;span&gt;x&lt;/span&gt;" data-line-start="2" data-line-end="2"/>
+    </svg>"#;
+    let (printed, callouts) = callouts(timeline, &Annotations::parse(MAIN_RS).unwrap()).unwrap();
+    let found: Vec<(usize, (usize, usize), Kind, &str)> = callouts
+        .iter()
+        .map(|c| (c.number, c.lines, c.kind, c.description.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (1, (2, 2), Kind::Event, "x is initialized"),
+            (2, (2, 2), Kind::Arrow, "Move from y to x"),
+            (3, (2, 4), Kind::Timeline, "x is the owner"),
+            (4, (4, 4), Kind::Event, "x goes out of scope"),
+        ]
+    );
+
+    let doc = Document::parse(&printed).unwrap();
+    assert_eq!(doc.root_element().attribute("width"), Some("294px"));
+    let markers: Vec<(&str, &str, &str)> = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("text") && n.parent().unwrap().has_tag_name("g"))
+        .map(|n| {
+            let circle = n.prev_sibling_element().unwrap();
+            (
+                circle.attribute("cx").unwrap(),
+                circle.attribute("cy").unwrap(),
+                n.text().unwrap(),
+            )
+        })
+        .collect();
+    // Markers sit next to what they number, however far apart the rows
+    // are drawn; the event and the arrow on line 2 sit side by side.
+    assert_eq!(
+        markers,
+        [
+            ("84", "90", "1"),
+            ("102", "90", "2"),
+            ("84", "145", "3"),
+            ("84", "200", "4")
+        ]
+    );
+}
+
+#[test]
+fn printable_copies_are_up_to_date() {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let manifest = Manifest::load(&layout.manifest()).unwrap();
+
+    let mut failures = Vec::new();
+    for example in manifest.examples.iter().filter(|e| e.visualization) {
+        let mut dirs = vec![layout.example_dir(&example.name)];
+        if example.modified {
+            dirs.push(layout.modified_dir(&example.name));
+        }
+        for dir in dirs {
+            let annotations = Annotations::for_copy(&layout, &example.name, &dir).unwrap();
+            let timeline = fs::read_to_string(dir.join("vis_timeline.svg")).unwrap();
+            let (printed, _) = callouts(&timeline, &annotations).unwrap();
+            let path = dir.join(PRINT_FILE);
+            if fs::read_to_string(&path).ok() != Some(printed) {
+                failures.push(format!(
+                    "{}: missing or out of date; run `rustviz-tutorial postprocess {}`",
+                    path.display(),
+                    example.name
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
@@ -0,0 +1,70 @@
+//! Compiles every `source.rs` under `code_examples` and `modified_examples`.
+//!
+//! An example has to compile unless examples.toml marks it `compile_fail`, in
+//! which case rustc has to reject it with the given error code.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rustviz_tutorial::rustc::compile;
+use rustviz_tutorial::{Layout, Manifest};
+
+fn layout() -> Layout {
+    Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap()
+}
+
+fn sources(dir: &Path) -> Vec<PathBuf> {
+    let mut sources: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path().join("source.rs"))
+        .filter(|source| source.is_file())
+        .collect();
+    sources.sort();
+    sources
+}
+
+#[test]
+fn examples_compile_unless_marked_compile_fail() {
+    let layout = layout();
+    let manifest = Manifest::load(&layout.manifest()).unwrap();
+    let out = tempfile::tempdir().unwrap();
+
+    let mut checked = 0;
+    let mut failures = Vec::new();
+    for (kind, dir) in [
+        ("code", layout.code_examples()),
+        ("modified", layout.modified_examples()),
+    ] {
+        for source in sources(&dir) {
+            let name = source.parent().unwrap().file_name().unwrap();
+            let name = name.to_str().unwrap();
+            let binary = out.path().join(format!("{kind}-{name}"));
+            let compilation = compile(&source, &binary).unwrap();
+            let compile_fail = manifest.get(name).and_then(|e| e.compile_fail.as_deref());
+            match (compile_fail, compilation.success()) {
+                (None, false) => failures.push(format!(
+                    "{} does not compile:\n{}",
+                    source.display(),
+                    compilation.stderr
+                )),
+                (Some(code), true) => failures.push(format!(
+                    "{} is marked compile_fail = {code:?} but compiles",
+                    source.display()
+                )),
+                (Some(code), false) if !compilation.error_codes().contains(&code) => {
+                    failures.push(format!(
+                        "{} is marked compile_fail = {code:?} but fails with {:?}:\n{}",
+                        source.display(),
+                        compilation.error_codes(),
+                        compilation.stderr
+                    ))
+                }
+                _ => {}
+            }
+            checked += 1;
+        }
+    }
+
+    assert!(checked > 0, "no examples found");
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
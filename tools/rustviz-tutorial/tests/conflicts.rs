@@ -0,0 +1,61 @@
+//! Checks that rustc rejects every `compile_fail` example with a borrow
+//! checker code for at least one conflict, and that visualized ones have the
+//! events rustc rejects drawn in the error style, as `rustviz-tutorial
+//! postprocess` leaves them.
+
+use std::fs;
+use std::path::Path;
+
+use rustviz_tutorial::annotations::Annotations;
+use rustviz_tutorial::conflict;
+use rustviz_tutorial::rustc::Runner;
+use rustviz_tutorial::{Layout, Manifest};
+
+#[test]
+fn rejected_examples_highlight_their_conflicts() {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let manifest = Manifest::load(&layout.manifest()).unwrap();
+    let mut runner = Runner::new().unwrap();
+
+    let mut failures = Vec::new();
+    for example in &manifest.examples {
+        let Some(code) = &example.compile_fail else {
+            continue;
+        };
+        let dir = layout.example_dir(&example.name);
+        // Without a visualization there is no code panel to line up with.
+        let annotations = if example.visualization {
+            Annotations::load(&dir).unwrap()
+        } else {
+            Annotations::default()
+        };
+        let source = fs::read_to_string(dir.join("source.rs")).unwrap();
+        let compilation = runner.compile(&dir.join("source.rs")).unwrap();
+        let conflicts = conflict::conflicts(compilation, &annotations, &source);
+        if conflicts.is_empty() {
+            if conflict::is_borrowck(code) {
+                failures.push(format!(
+                    "{}: rustc reports no borrow checker error, but examples.toml expects {code}",
+                    example.name
+                ));
+            }
+            continue;
+        }
+        if !example.visualization {
+            continue;
+        }
+        let timeline = fs::read_to_string(dir.join("vis_timeline.svg")).unwrap();
+        let code = fs::read_to_string(dir.join("vis_code.svg")).unwrap();
+        let highlighted = conflict::highlight_timeline(&timeline, &conflicts).unwrap() == timeline
+            && conflict::highlight_code(&code, &conflicts).unwrap() == code;
+        if !highlighted {
+            failures.push(format!(
+                "{}: conflicts are not highlighted; run `rustviz-tutorial postprocess {}`",
+                dir.display(),
+                example.name
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
@@ -0,0 +1,92 @@
+//! Finding `{{#rustviz ...}}` directives in a chapter.
+
+use rustviz_tutorial::directive::{find, unescape, Kind};
+
+#[test]
+fn every_kind_of_directive_is_found() {
+    let text = "{{#rustviz move_assignment}} {{#include x.rs}}\n\
+                {{#rustviz move_assignment modified}}\n\
+                {{#rustviz_output printing \"x = 1\\n\"}}\n\
+                {{#rustviz_error use_after_move}}";
+    let found: Vec<(&str, String, Kind)> = find(text)
+        .unwrap()
+        .into_iter()
+        .map(|d| (&text[d.range], d.name, d.kind))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (
+                "{{#rustviz move_assignment}}",
+                "move_assignment".to_owned(),
+                Kind::Visualization { modified: false }
+            ),
+            (
+                "{{#rustviz move_assignment modified}}",
+                "move_assignment".to_owned(),
+                Kind::Visualization { modified: true }
+            ),
+            (
+                "{{#rustviz_output printing \"x = 1\\n\"}}",
+                "printing".to_owned(),
+                Kind::Output {
+                    expected: Some("x = 1\n".to_owned())
+                }
+            ),
+            (
+                "{{#rustviz_error use_after_move}}",
+                "use_after_move".to_owned(),
+                Kind::Error
+            ),
+        ]
+    );
+}
+
+#[test]
+fn a_quoted_string_may_hold_closing_braces() {
+    let text = r#"{{#rustviz_output braces "{{}} \"}}\""}} after"#;
+    let found = find(text).unwrap();
+    assert_eq!(found.len(), 1);
+    assert_eq!(&text[found[0].range.end..], " after");
+    assert_eq!(
+        found[0].kind,
+        Kind::Output {
+            expected: Some("{{}} \"}}\"".to_owned())
+        }
+    );
+}
+
+#[test]
+fn escaped_directives_are_skipped_and_unescaped() {
+    let text = r"\{{#rustviz move_assignment}} \{{#rustviz_output}} \{{#include x.rs}}";
+    assert_eq!(find(text).unwrap(), []);
+    assert_eq!(
+        unescape(text),
+        r"{{#rustviz move_assignment}} {{#rustviz_output}} \{{#include x.rs}}"
+    );
+}
+
+#[test]
+fn malformed_directives_are_errors() {
+    for (text, error) in [
+        (
+            "{{#rustviz move_assignment",
+            "unterminated `{{#rustviz` directive at byte 0",
+        ),
+        (
+            "{{#rustviz_output printing \"x = 1}}",
+            "unterminated `{{#rustviz_output` directive at byte 0",
+        ),
+        ("{{#rustviz}}", "`{{#rustviz}}` is missing an example name"),
+        (
+            "{{#rustviz move_assignment changed}}",
+            "unexpected option `changed` in `{{#rustviz move_assignment changed}}`",
+        ),
+        (
+            "{{#rustviz_error use_after_move \"E0382\"}}",
+            "unexpected string \"E0382\" in `{{#rustviz_error use_after_move \"E0382\"}}`",
+        ),
+    ] {
+        assert_eq!(find(text).unwrap_err().to_string(), error, "{text}");
+    }
+}
@@ -0,0 +1,71 @@
+//! Matches the elements of a timeline to the events in its `main.rs`.
+
+use roxmltree::Document;
+use rustviz_tutorial::annotations::Annotations;
+use rustviz_tutorial::event_order::order;
+use rustviz_tutorial::svg;
+use rustviz_tutorial::timeline::{Kind, Timeline};
+
+const MAIN_RS: &str = "/* --- BEGIN Variable Definitions ---
+Owner x;
+Owner y;
+--- END Variable Definitions --- */
+fn main() {
+    let x = 5; // !{ Bind(x) }
+    let y = x; // !{ Copy(x->y) }
+} /* !{ GoOutOfScope(y), GoOutOfScope(x) } */
+";
+
+/// Drawn column by column, so neither the document order nor the positions
+/// say that `y` goes out of scope before `x`.
+const TIMELINE: &str = r#"<svg xmlns="http://www.w3.org/2000/svg">
+    <text x="70" y="70" class="label tooltip-trigger" data-hash="1" data-tooltip-text="x">x</text>
+    <text x="110" y="70" class="label tooltip-trigger" data-hash="2" data-tooltip-text="y">y</text>
+    <circle cx="70" cy="175" r="5" data-hash="1" class="tooltip-trigger" data-line-start="4" data-line-end="4" data-tooltip-text="x goes out of scope"/>
+    <line x1="70" y1="115" x2="70" y2="175" data-hash="1" class="tooltip-trigger" data-line-start="2" data-line-end="4" data-tooltip-text="x is the owner"/>
+    <circle cx="70" cy="115" r="5" data-hash="1" class="tooltip-trigger" data-line-start="2" data-line-end="2" data-tooltip-text="x is initialized"/>
+    <circle cx="110" cy="175" r="5" data-hash="2" class="tooltip-trigger" data-line-start="4" data-line-end="4" data-tooltip-text="y goes out of scope"/>
+    <circle cx="110" cy="145" r="5" data-hash="2" class="tooltip-trigger" data-line-start="3" data-line-end="3" data-tooltip-text="y is initialized"/>
+    <polyline points="70,145 105,145" class="tooltip-trigger" data-line-start="3" data-line-end="3" data-tooltip-text="Copy from &lt;span&gt;x&lt;/span&gt; to &lt;span&gt;y&lt;/span&gt;"/>
+</svg>"#;
+
+#[test]
+fn elements_follow_the_events_in_main_rs() {
+    let annotations = Annotations::parse(MAIN_RS).unwrap();
+    let doc = Document::parse(TIMELINE).unwrap();
+    let timeline = Timeline::new(&doc);
+    let found: Vec<(Option<&str>, Kind, String)> = order(&timeline, &annotations)
+        .unwrap()
+        .iter()
+        .map(|o| {
+            (
+                o.event.map(|i| annotations.events[i].name.as_str()),
+                o.element.kind,
+                svg::plain_text(o.element.tooltip()),
+            )
+        })
+        .collect();
+    let row = |event, kind, tooltip: &str| (event, kind, tooltip.to_owned());
+    assert_eq!(
+        found,
+        [
+            row(Some("Bind"), Kind::Event, "x is initialized"),
+            row(None, Kind::Timeline, "x is the owner"),
+            row(Some("Copy"), Kind::Event, "y is initialized"),
+            row(Some("Copy"), Kind::Arrow, "Copy from x to y"),
+            row(Some("GoOutOfScope"), Kind::Event, "y goes out of scope"),
+            row(Some("GoOutOfScope"), Kind::Event, "x goes out of scope"),
+        ]
+    );
+}
+
+#[test]
+fn an_element_drawing_no_event_fails() {
+    let annotations = Annotations::parse(&MAIN_RS.replace("Copy(x->y)", "Bind(y)")).unwrap();
+    let doc = Document::parse(TIMELINE).unwrap();
+    let err = order(&Timeline::new(&doc), &annotations).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the move, copy or borrow \"Copy from x to y\" on line 3 draws none of the events main.rs has there"
+    );
+}
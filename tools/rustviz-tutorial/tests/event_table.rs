@@ -0,0 +1,60 @@
+//! Reads the events of a timeline into the rows of its accessible table.
+
+use std::fs;
+use std::path::Path;
+
+use rustviz_tutorial::annotations::Annotations;
+use rustviz_tutorial::event_table::{rows, Row};
+use rustviz_tutorial::timeline::Kind;
+use rustviz_tutorial::{Layout, Manifest};
+
+const MAIN_RS: &str = "/* --- BEGIN Variable Definitions ---
+Owner x;
+--- END Variable Definitions --- */
+fn main() {
+    let x = 5; // !{ Bind(x) }
+    println!(\"{}\", x);
+} /* !{ GoOutOfScope(x) } */
+";
+
+#[test]
+fn rows_follow_the_events() {
+    let timeline = r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <text x="70" y="70" class="label tooltip-trigger" data-hash="1" data-tooltip-text="x, immutable">x</text>
+        <path class="hollow tooltip-trigger" data-hash="1" d="M 68.2,115 V 175 h 3.5 V 115 h -3.5" data-line-start="2" data-line-end="4" data-tooltip-text="&lt;span&gt;x&lt;/span&gt; is the owner"/>
+        <circle cx="70" cy="175" r="5" data-hash="1" class="tooltip-trigger" data-line-start="4" data-line-end="4" data-tooltip-text="x goes out of scope"/>
+        <circle cx="70" cy="115" r="5" data-hash="1" class="tooltip-trigger" data-line-start="2" data-line-end="2" data-tooltip-text="x is initialized"/>
+    </svg>"#;
+    let row = |lines, kind, description: &str| Row {
+        lines: Some(lines),
+        variable: Some("x".to_owned()),
+        kind,
+        description: description.to_owned(),
+    };
+    assert_eq!(
+        rows(timeline, &Annotations::parse(MAIN_RS).unwrap()).unwrap(),
+        [
+            row((2, 2), Kind::Event, "x is initialized"),
+            row((2, 4), Kind::Timeline, "x is the owner"),
+            row((4, 4), Kind::Event, "x goes out of scope"),
+        ]
+    );
+}
+
+#[test]
+fn every_event_names_its_variable() {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let manifest = Manifest::load(&layout.manifest()).unwrap();
+    for example in manifest.examples.iter().filter(|e| e.visualization) {
+        let dir = layout.example_dir(&example.name);
+        let annotations = Annotations::load(&dir).unwrap();
+        let path = dir.join("vis_timeline.svg");
+        let rows = rows(&fs::read_to_string(&path).unwrap(), &annotations).unwrap();
+        assert!(
+            rows.iter()
+                .all(|row| row.variable.is_some() || row.kind == Kind::StructBox),
+            "{}: {rows:#?}",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,37 @@
+//! Runs every example with a `stdout` in examples.toml and compares what it
+//! prints.
+
+use std::path::Path;
+
+use rustviz_tutorial::rustc::Runner;
+use rustviz_tutorial::{Layout, Manifest};
+
+#[test]
+fn examples_print_their_manifest_stdout() {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let manifest = Manifest::load(&layout.manifest()).unwrap();
+    let mut runner = Runner::new().unwrap();
+
+    let mut failures = Vec::new();
+    for example in &manifest.examples {
+        let Some(expected) = &example.stdout else {
+            continue;
+        };
+        let mut sources = vec![layout.example_dir(&example.name).join("source.rs")];
+        if example.modified {
+            sources.push(layout.modified_dir(&example.name).join("source.rs"));
+        }
+        for source in sources {
+            match runner.stdout(&source) {
+                Ok(actual) if actual == expected => {}
+                Ok(actual) => failures.push(format!(
+                    "{} printed {actual:?}, examples.toml says {expected:?}",
+                    source.display()
+                )),
+                Err(err) => failures.push(format!("{err:#}")),
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
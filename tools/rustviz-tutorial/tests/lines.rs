@@ -0,0 +1,167 @@
+//! Checks that every visualized example's timeline carries the source lines
+//! of its tooltip triggers, as `rustviz-tutorial postprocess` leaves them,
+//! and that the lines are the ones its `main.rs` annotates.
+
+use std::fs;
+use std::path::Path;
+
+use roxmltree::Document;
+use rustviz_tutorial::annotations::Annotations;
+use rustviz_tutorial::lines::{self, LINE_END, LINE_START};
+use rustviz_tutorial::svg;
+use rustviz_tutorial::{Layout, Manifest};
+
+#[test]
+fn timelines_are_annotated_with_lines() {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let manifest = Manifest::load(&layout.manifest()).unwrap();
+
+    let mut failures = Vec::new();
+    for example in &manifest.examples {
+        if !example.visualization {
+            continue;
+        }
+        let mut dirs = vec![layout.example_dir(&example.name)];
+        if example.modified {
+            dirs.push(layout.modified_dir(&example.name));
+        }
+        for dir in dirs {
+            let annotations = Annotations::for_copy(&layout, &example.name, &dir).unwrap();
+            let path = dir.join("vis_timeline.svg");
+            let timeline = fs::read_to_string(&path).unwrap();
+            match lines::annotate(&timeline, &annotations) {
+                Ok(annotated) if annotated == timeline => {}
+                Ok(_) => failures.push(format!(
+                    "{}: lines are missing or out of date; run `rustviz-tutorial postprocess {}`",
+                    path.display(),
+                    example.name
+                )),
+                Err(err) => failures.push(format!("{}: {err:#}", path.display())),
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+/// The svg_generator draws the code panel from `main.rs`, one row per line
+/// plus a blank one after some lines with events, and `helpers.js` counts
+/// rows to find a line. So `main.rs` has to have the panel's blank rows, and
+/// otherwise be `source.rs` with its events.
+#[test]
+fn main_rs_matches_source_rs_and_the_code_panel() {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let manifest = Manifest::load(&layout.manifest()).unwrap();
+    let code = |text: &str| -> Vec<String> {
+        text.lines()
+            .map(|line| line.trim_end().to_owned())
+            .filter(|line| !line.is_empty())
+            .collect()
+    };
+
+    let mut failures = Vec::new();
+    for example in manifest.examples.iter().filter(|e| e.visualization) {
+        let dir = layout.example_dir(&example.name);
+        let annotations = Annotations::load(&dir).unwrap();
+        let source = fs::read_to_string(dir.join("source.rs")).unwrap();
+        if code(&annotations.source.join("\n")) != code(&source) {
+            failures.push(format!(
+                "{}: main.rs without its events is not source.rs",
+                example.name
+            ));
+        }
+        let panel = fs::read_to_string(dir.join("vis_code.svg")).unwrap();
+        let panel = Document::parse(&panel).unwrap();
+        let rows = svg::group(&panel, "code")
+            .unwrap()
+            .children()
+            .filter(|n| svg::has_class(*n, "code"))
+            .count();
+        if rows != annotations.source.len() {
+            failures.push(format!(
+                "{}: the code panel has {rows} rows but main.rs has {} lines",
+                example.name,
+                annotations.source.len()
+            ));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+/// Rows 40 and 70 units apart, and events on lines 2, 5 and 6: nothing
+/// about the spacing says which line a row is.
+const TIMELINE: &str = r#"<svg xmlns="http://www.w3.org/2000/svg">
+    <text x="70" y="70" data-hash="1" class="label tooltip-trigger" data-tooltip-text="x">x</text>
+    <circle cx="70" cy="100" r="5" data-hash="1" class="tooltip-trigger" data-tooltip-text="x is initialized"/>
+    <text x="96" y="105" data-hash="3" class="functionLogo tooltip-trigger" data-tooltip-text="f()">f</text>
+    <path data-hash="1" class="hollow tooltip-trigger" d="M 68.2,100 V 140 h 3.5 V 100 h -3.5" data-tooltip-text="x"/>
+    <path data-hash="2" class="staticref tooltip-trigger" d="M 160 140 l 15 12 v 46 l -15 12" data-tooltip-text="cannot mutate *y"/>
+    <polyline points="70,140 150,140" class="tooltip-trigger" data-tooltip-text="borrowed" data-line-start="1" data-line-end="1"/>
+    <use x="160" y="210" data-hash="2" class="tooltip-trigger" data-tooltip-text="f() reads from y"/>
+</svg>"#;
+
+const MAIN_RS: &str = "/* --- BEGIN Variable Definitions ---
+Owner x;
+StaticRef y;
+Function f();
+--- END Variable Definitions --- */
+fn main() {
+    let x = 5; // !{ Bind(x) }
+
+
+    let y = &x; // !{ StaticBorrow(x->y) }
+    f(y); // reads !{ PassByStaticReference(y->f()) }
+}
+";
+
+#[test]
+fn lines_come_from_the_annotations() {
+    let annotations = Annotations::parse(MAIN_RS).unwrap();
+    assert_eq!(annotations.lines(), [2, 5, 6]);
+    assert_eq!(annotations.source[5], "    f(y); // reads");
+
+    let annotated = lines::annotate(TIMELINE, &annotations).unwrap();
+    assert_eq!(
+        lines::annotate(&annotated, &annotations).unwrap(),
+        annotated
+    );
+
+    let doc = Document::parse(&annotated).unwrap();
+    let found: Vec<(Option<&str>, Option<&str>)> = doc
+        .descendants()
+        .filter(|n| svg::has_class(*n, "tooltip-trigger"))
+        .map(|n| (n.attribute(LINE_START), n.attribute(LINE_END)))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (None, None),
+            (Some("2"), Some("2")),
+            (Some("2"), Some("2")),
+            (Some("2"), Some("5")),
+            (Some("5"), Some("6")),
+            (Some("5"), Some("5")),
+            (Some("6"), Some("6")),
+        ]
+    );
+    assert!(annotated.contains(r#"data-tooltip-text="x is initialized" data-line-start="2""#));
+}
+
+#[test]
+fn disagreeing_with_the_annotations_fails() {
+    let mut annotations = Annotations::parse(MAIN_RS).unwrap();
+    annotations.events.pop();
+    let err = lines::annotate(TIMELINE, &annotations).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "the timeline has 3 rows of events but main.rs has events on 2 lines"
+    );
+
+    let between = TIMELINE.replace("v 46", "v 30");
+    let err = lines::annotate(&between, &Annotations::parse(MAIN_RS).unwrap()).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "<path> \"cannot mutate *y\" from y = 140 to 194 does not start and end on rows of events"
+    );
+}
@@ -0,0 +1,147 @@
+//! Optimizes a copy of the book's SVGs and checks that the drawing is the
+//! same as before.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use roxmltree::{Document, Node};
+use rustviz_tutorial::callouts::PRINT_FILE;
+use rustviz_tutorial::optimize::{optimize, STYLESHEET};
+use rustviz_tutorial::Layout;
+use tempfile::TempDir;
+
+/// Copies the book's example SVGs into a scratch book with one page showing
+/// the `copy` example and the printable timeline of `move_assignment`.
+fn scratch_book() -> TempDir {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let book = TempDir::new().unwrap();
+    for entry in fs::read_dir(layout.code_examples()).unwrap() {
+        let example = entry.unwrap().path();
+        let dir = book
+            .path()
+            .join("assets/code_examples")
+            .join(example.file_name().unwrap());
+        fs::create_dir_all(&dir).unwrap();
+        for file in ["vis_code.svg", "vis_timeline.svg", PRINT_FILE] {
+            if example.join(file).exists() {
+                fs::copy(example.join(file), dir.join(file)).unwrap();
+            }
+        }
+    }
+    fs::write(
+        book.path().join("ownership.html"),
+        r#"<object type="image/svg+xml" data="./assets/code_examples/copy/vis_code.svg"></object>
+<object type="image/svg+xml" data="./assets/code_examples/copy/vis_timeline.svg"></object>
+<img class="rustviz-print" src="./assets/code_examples/move_assignment/vis_timeline_print.svg">"#,
+    )
+    .unwrap();
+    book
+}
+
+/// Every element outside `<style>` with its attributes, and the text drawn
+/// in it. `<defs>` entries without an id in `keep` are left out.
+fn drawing(doc: &Document, keep: &BTreeSet<String>) -> Vec<String> {
+    fn walk(node: Node, keep: &BTreeSet<String>, out: &mut Vec<String>) {
+        for child in node.children() {
+            if child.is_text() && (node.has_tag_name("text") || node.has_tag_name("tspan")) {
+                out.push(child.text().unwrap_or_default().to_owned());
+            }
+            if !child.is_element() || child.has_tag_name("style") {
+                continue;
+            }
+            if node.has_tag_name("defs")
+                && child.attribute("id").is_some_and(|id| !keep.contains(id))
+            {
+                continue;
+            }
+            let attributes: Vec<String> = child
+                .attributes()
+                .map(|a| format!("{}={}", a.name(), a.value()))
+                .collect();
+            out.push(format!(
+                "<{} {}>",
+                child.tag_name().name(),
+                attributes.join(" ")
+            ));
+            walk(child, keep, out);
+        }
+    }
+    let mut out = Vec::new();
+    walk(doc.root(), keep, &mut out);
+    out
+}
+
+#[test]
+fn keeps_the_drawing_and_shrinks_the_svgs() {
+    let book = scratch_book();
+    let mut originals = Vec::new();
+    for entry in fs::read_dir(book.path().join("assets/code_examples")).unwrap() {
+        for file in fs::read_dir(entry.unwrap().path()).unwrap() {
+            let path = file.unwrap().path();
+            if path.file_name() != Some(PRINT_FILE.as_ref()) {
+                originals.push((path.clone(), fs::read_to_string(path).unwrap()));
+            }
+        }
+    }
+    let printed = book
+        .path()
+        .join("assets/code_examples/move_assignment")
+        .join(PRINT_FILE);
+    let printed_before = fs::read_to_string(&printed).unwrap();
+
+    let report = optimize(book.path()).unwrap();
+    assert_eq!(report.files.len(), originals.len());
+    assert!(report.after() < report.before());
+    assert_eq!(report.pages.len(), 1);
+    assert_eq!(report.pages[0].page, "ownership.html");
+    // The printable copy is an image, so it keeps its own stylesheet, but
+    // the page still loads it.
+    assert_eq!(report.pages[0].svgs, 3);
+    assert_eq!(fs::read_to_string(&printed).unwrap(), printed_before);
+    let shown: u64 = report
+        .files
+        .iter()
+        .filter(|f| f.path.starts_with("assets/code_examples/copy/"))
+        .map(|f| f.after)
+        .sum();
+    assert_eq!(
+        report.pages[0].after,
+        shown + report.stylesheet + printed_before.len() as u64
+    );
+
+    let stylesheet = fs::read_to_string(book.path().join(STYLESHEET)).unwrap();
+    assert!(stylesheet.contains(r#"[data-hash="1"]{fill:#1893ff;stroke:#1893ff}"#));
+    assert!(!stylesheet.contains(".flex-container"));
+    assert!(!stylesheet.contains("url(#"));
+
+    for (path, original) in &originals {
+        let optimized = fs::read_to_string(path).unwrap();
+        assert!(
+            optimized.contains("@import url(../../../assets/rustviz-svg.css);"),
+            "{}",
+            path.display()
+        );
+        let optimized = Document::parse(&optimized).unwrap();
+        let ids: BTreeSet<String> = optimized
+            .descendants()
+            .filter_map(|n| n.attribute("id"))
+            .map(str::to_owned)
+            .collect();
+        assert_eq!(
+            drawing(&optimized, &ids),
+            drawing(&Document::parse(original).unwrap(), &ids),
+            "{}",
+            path.display()
+        );
+    }
+
+    let optimized: Vec<String> = originals
+        .iter()
+        .map(|(path, _)| fs::read_to_string(path).unwrap())
+        .collect();
+    assert!(optimize(book.path()).unwrap().files.is_empty());
+    for ((path, _), before) in originals.iter().zip(&optimized) {
+        assert_eq!(&fs::read_to_string(path).unwrap(), before);
+    }
+}
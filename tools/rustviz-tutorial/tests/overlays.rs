@@ -0,0 +1,98 @@
+//! Checks the overlays in the tree are applied and still match their SVGs,
+//! and that applying an overlay twice is the same as applying it once.
+
+use std::fs;
+use std::path::Path;
+
+use rustviz_tutorial::overlay::{Overlay, OVERLAY_FILE};
+use rustviz_tutorial::postprocess::stale_overlays;
+use rustviz_tutorial::{Layout, Manifest};
+
+fn layout() -> Layout {
+    Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap()
+}
+
+#[test]
+fn overlays_are_applied_and_not_stale() {
+    let layout = layout();
+    let manifest = Manifest::load(&layout.manifest()).unwrap();
+
+    let mut failures = Vec::new();
+    for example in &manifest.examples {
+        for stale in stale_overlays(&layout, example).unwrap() {
+            failures.push(format!("{}: {}", stale.overlay.display(), stale.stale));
+        }
+        for dir in [
+            layout.example_dir(&example.name),
+            layout.modified_dir(&example.name),
+        ] {
+            let Some(overlay) = Overlay::load(&dir).unwrap() else {
+                continue;
+            };
+            let svg = fs::read_to_string(dir.join("vis_timeline.svg")).unwrap();
+            if overlay.apply(&svg).unwrap().0 != svg {
+                failures.push(format!(
+                    "{} is not applied; run `rustviz-tutorial postprocess {}`",
+                    dir.join(OVERLAY_FILE).display(),
+                    example.name
+                ));
+            }
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+#[test]
+fn applying_an_overlay_twice_changes_nothing() {
+    let svg = fs::read_to_string(
+        layout()
+            .example_dir("move_assignment")
+            .join("vis_timeline.svg"),
+    )
+    .unwrap();
+    let overlay: Overlay = toml::from_str(
+        r#"
+        [[tooltip]]
+        line = 4
+        variable = "y"
+        append = "; the resource previously owned is dropped"
+
+        [[tooltip]]
+        line = 2
+        variable = "x"
+        text = "`x` is bound to a new String"
+
+        [[event]]
+        line = 4
+        variable = "y"
+        text = "`y`'s old resource is dropped"
+
+        [[label]]
+        line = 3
+        variable = "y"
+        text = "`mut`"
+
+        [[tooltip]]
+        line = 9
+        variable = "x"
+        append = "never applies"
+        "#,
+    )
+    .unwrap();
+
+    let (once, stale) = overlay.apply(&svg).unwrap();
+    assert_ne!(once, svg);
+    assert!(once.contains("previously owned is dropped"));
+    assert!(once.contains("data-overlay=\"event 4 y\""));
+    assert!(once.contains("data-overlay=\"label 3 y\""));
+    assert_eq!(stale.len(), 1, "{stale:?}");
+    assert_eq!(
+        stale[0].to_string(),
+        "tooltip for the event of `x` on line 9 matches nothing"
+    );
+
+    let (twice, stale_again) = overlay.apply(&once).unwrap();
+    assert_eq!(twice, once);
+    assert_eq!(stale_again, stale);
+}
@@ -0,0 +1,82 @@
+//! The build report, as a table and as JSON, for a small manifest.
+
+use std::fs;
+
+use rustviz_tutorial::report::{ExampleReport, Report, Status};
+use rustviz_tutorial::Manifest;
+use serde_json::json;
+use tempfile::TempDir;
+
+const MANIFEST: &str = r#"
+[[example]]
+name = "copy"
+chapter = "ownership.md"
+purpose = "Integers are copied, not moved."
+
+[[example]]
+name = "struct_lifetime"
+chapter = "structs.md"
+purpose = "A struct holding a reference cannot outlive it."
+"#;
+
+/// A report for the manifest's examples and one it does not list, as
+/// `rustviz-tutorial build` makes it.
+fn report() -> Report {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("examples.toml");
+    fs::write(&path, MANIFEST).unwrap();
+    let manifest = Manifest::load(&path).unwrap();
+
+    let mut report = Report::default();
+    for name in ["copy", "struct_lifetime", "typo"] {
+        report.push(match manifest.get(name) {
+            Some(example) if example.name == "copy" => ExampleReport::new(name, Status::Built),
+            Some(_) => ExampleReport::new(name, Status::SvgFailed)
+                .with_detail("error: unknown event\n  --> main.rs:4"),
+            None => ExampleReport::new(name, Status::NotInManifest),
+        });
+    }
+    report
+}
+
+#[test]
+fn the_table_lists_every_example_and_the_count_built() {
+    let report = report();
+    assert!(!report.is_ok());
+
+    let mut out = Vec::new();
+    report.write_table(&mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "copy             built
+struct_lifetime  svg failed
+    error: unknown event
+      --> main.rs:4
+typo             not in manifest
+
+1 of 3 examples built
+"
+    );
+}
+
+#[test]
+fn the_json_has_a_status_and_detail_per_example() {
+    let mut out = Vec::new();
+    report().write_json(&mut out).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(
+        value,
+        json!({
+            "examples": [
+                {"name": "copy", "status": "built"},
+                {
+                    "name": "struct_lifetime",
+                    "status": "svg_failed",
+                    "detail": "error: unknown event\n  --> main.rs:4",
+                },
+                {"name": "typo", "status": "not_in_manifest"},
+            ]
+        })
+    );
+    assert!(out.ends_with(b"}\n"));
+}
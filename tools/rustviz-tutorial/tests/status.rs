@@ -0,0 +1,184 @@
+//! Checks every modified example was made from the `source.rs` it sits next
+//! to in `code_examples`, how `status` tells which copy of a scratch
+//! example is newer, and that it checks a transcription against upstream's
+//! `main.rs`.
+
+use std::fs::{self, File};
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+use rustviz_tutorial::annotations::TRANSCRIPTION;
+use rustviz_tutorial::layout::Upstream;
+use rustviz_tutorial::manifest::Example;
+use rustviz_tutorial::rustc::Runner;
+use rustviz_tutorial::status::{status, ModifiedStatus, UpstreamStatus};
+use rustviz_tutorial::{Layout, Manifest};
+use tempfile::TempDir;
+
+#[test]
+fn modified_examples_match_their_base() {
+    let layout = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let manifest = Manifest::load(&layout.manifest()).unwrap();
+    let mut runner = Runner::new().unwrap();
+
+    let mut failures = Vec::new();
+    for example in manifest.examples.iter().filter(|e| e.modified) {
+        let status = status(&layout, None, example, &mut runner).unwrap();
+        if status.modified != Some(ModifiedStatus::MatchesBase) {
+            failures.push(format!("{}: {:?}", example.name, status.modified));
+        }
+    }
+
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
+
+/// A commit of everything in `dir` at Unix time `time`, starting a
+/// repository there if there is none.
+fn commit(dir: &Path, time: u64) {
+    let date = format!("@{time} +0000");
+    let git = |args: &[&str]| {
+        let status = Command::new("git")
+            .args(["-c", "user.name=Test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .env("GIT_AUTHOR_DATE", &date)
+            .env("GIT_COMMITTER_DATE", &date)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(status.status.success(), "git {args:?}: {status:?}");
+    };
+    if !dir.join(".git").exists() {
+        git(&["init", "-q"]);
+    }
+    git(&["add", "-A"]);
+    git(&["commit", "-q", "-m", "change"]);
+}
+
+fn example(name: &str, modified: bool) -> Example {
+    Example {
+        name: name.to_owned(),
+        chapter: None,
+        purpose: String::new(),
+        stdout: None,
+        visualization: false,
+        modified,
+        compile_fail: None,
+    }
+}
+
+#[test]
+fn the_side_committed_last_is_newer() {
+    let (book, rustviz) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    let (layout, upstream) = (Layout::new(book.path()), Upstream::new(rustviz.path()));
+    let example = example("copy", false);
+    let (local, remote) = (layout.example_dir("copy"), upstream.example_dir("copy"));
+    fs::create_dir_all(&local).unwrap();
+    fs::create_dir_all(&remote).unwrap();
+    let mut runner = Runner::new().unwrap();
+    let mut check = |expected| {
+        let status = status(&layout, Some(&upstream), &example, &mut runner).unwrap();
+        assert_eq!(status.upstream, Some(expected));
+        if expected != UpstreamStatus::InSync {
+            assert_eq!(status.differing, ["source.rs"]);
+        }
+    };
+
+    fs::write(local.join("source.rs"), "fn main() {}\n").unwrap();
+    commit(book.path(), 1_000);
+    fs::write(remote.join("source.rs"), "fn main() {}\n").unwrap();
+    commit(rustviz.path(), 2_000);
+    check(UpstreamStatus::InSync);
+
+    // File times say the book's copy is newer; the history says otherwise.
+    fs::write(remote.join("source.rs"), "fn main() { let x = 5; }\n").unwrap();
+    commit(rustviz.path(), 3_000);
+    let later = SystemTime::now() + Duration::from_secs(3600);
+    File::options()
+        .write(true)
+        .open(local.join("source.rs"))
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+    check(UpstreamStatus::NewerUpstream);
+
+    fs::write(local.join("source.rs"), "fn main() { let y = 5; }\n").unwrap();
+    check(UpstreamStatus::LocalChanges);
+    commit(book.path(), 4_000);
+    check(UpstreamStatus::LocalChanges);
+}
+
+#[test]
+fn a_modified_example_whose_base_changed_differs() {
+    let book = TempDir::new().unwrap();
+    let layout = Layout::new(book.path());
+    let example = example("move_assignment", true);
+    let (base, modified) = (
+        layout.example_dir("move_assignment"),
+        layout.modified_dir("move_assignment"),
+    );
+    fs::create_dir_all(&base).unwrap();
+    fs::create_dir_all(&modified).unwrap();
+    fs::write(base.join("source.rs"), "fn main() {}\n").unwrap();
+    fs::write(modified.join("source.rs"), "fn main() {}\n").unwrap();
+    let mut runner = Runner::new().unwrap();
+
+    let status_of = |runner: &mut Runner| status(&layout, None, &example, runner).unwrap();
+    assert_eq!(
+        status_of(&mut runner).modified,
+        Some(ModifiedStatus::MatchesBase)
+    );
+    fs::write(base.join("source.rs"), "fn main() { let x = 5; }\n").unwrap();
+    assert_eq!(
+        status_of(&mut runner).modified,
+        Some(ModifiedStatus::SourceDiffers)
+    );
+    fs::remove_dir_all(&modified).unwrap();
+    assert_eq!(
+        status_of(&mut runner).modified,
+        Some(ModifiedStatus::Missing)
+    );
+}
+
+#[test]
+fn a_transcription_is_checked_against_upstream() {
+    let repo = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let (book, rustviz) = (TempDir::new().unwrap(), TempDir::new().unwrap());
+    let (layout, upstream) = (Layout::new(book.path()), Upstream::new(rustviz.path()));
+    let example = Example {
+        visualization: true,
+        ..example("string_from", false)
+    };
+    let (local, remote) = (
+        layout.example_dir("string_from"),
+        upstream.example_dir("string_from"),
+    );
+    fs::create_dir_all(&local).unwrap();
+    fs::create_dir_all(&remote).unwrap();
+    let transcription =
+        fs::read_to_string(repo.example_dir("string_from").join(TRANSCRIPTION)).unwrap();
+    for file in ["source.rs", "vis_code.svg", "vis_timeline.svg"] {
+        let from = repo.example_dir("string_from").join(file);
+        fs::copy(&from, local.join(file)).unwrap();
+        fs::copy(&from, remote.join(file)).unwrap();
+    }
+    fs::write(local.join(TRANSCRIPTION), &transcription).unwrap();
+    let header = transcription.split_once("/* ---").unwrap().1;
+    fs::write(remote.join("main.rs"), format!("/* ---{header}")).unwrap();
+    commit(book.path(), 1_000);
+    commit(rustviz.path(), 2_000);
+    let mut runner = Runner::new().unwrap();
+
+    let status_of =
+        |runner: &mut Runner| status(&layout, Some(&upstream), &example, runner).unwrap();
+    assert_eq!(
+        status_of(&mut runner).upstream,
+        Some(UpstreamStatus::InSync)
+    );
+
+    let moved = header.replace("Move(String::from()->s)", "Copy(String::from()->s)");
+    fs::write(remote.join("main.rs"), format!("/* ---{moved}")).unwrap();
+    let status = status_of(&mut runner);
+    assert_eq!(status.upstream, Some(UpstreamStatus::NewerUpstream));
+    assert_eq!(status.differing, [TRANSCRIPTION]);
+}
@@ -0,0 +1,47 @@
+//! Orders the events of a timeline into playback steps.
+
+use rustviz_tutorial::annotations::Annotations;
+use rustviz_tutorial::steps::steps;
+use rustviz_tutorial::timeline::Kind;
+
+const MAIN_RS: &str = "/* --- BEGIN Variable Definitions ---
+Owner x;
+Owner y;
+--- END Variable Definitions --- */
+fn main() {
+    let x = y; // !{ Move(y->x) }
+    println!(\"{}\", x);
+} /* !{ GoOutOfScope(x) } */
+";
+
+#[test]
+fn steps_follow_the_events() {
+    let timeline = r#"<svg xmlns="http://www.w3.org/2000/svg">
+        <text x="70" y="70" class="label tooltip-trigger" data-hash="1" data-tooltip-text="x, immutable">x</text>
+        <path class="hollow tooltip-trigger" data-hash="1" d="M 68.2,115 V 175 h 3.5 V 115 h -3.5" data-line-start="2" data-line-end="4" data-tooltip-text="x is the owner"/>
+        <circle cx="70" cy="175" r="5" data-hash="1" class="tooltip-trigger" data-line-start="4" data-line-end="4" data-tooltip-text="x goes out of scope"/>
+        <circle cx="70" cy="115" r="5" data-hash="1" class="tooltip-trigger" data-line-start="2" data-line-end="2" data-tooltip-text="&lt;span&gt;x&lt;/span&gt; is initialized"/>
+        <polyline points="130,115 80,115" class="tooltip-trigger" data-tooltip-text="Move from &lt;span&gt;y&lt;/span&gt; to &lt;span&gt;x&lt;/span&gt;" data-line-start="2" data-line-end="2"/>
+    </svg>"#;
+    let steps = steps(timeline, &Annotations::parse(MAIN_RS).unwrap()).unwrap();
+    let found: Vec<(usize, Kind, &str, &[usize])> = steps
+        .iter()
+        .map(|s| {
+            (
+                s.line,
+                s.kind,
+                s.description.as_str(),
+                s.elements.as_slice(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        found,
+        [
+            (2, Kind::Event, "x is initialized", &[3, 1][..]),
+            (2, Kind::Arrow, "Move from y to x", &[4, 1][..]),
+            (4, Kind::Event, "x goes out of scope", &[2, 1][..]),
+        ]
+    );
+    assert!(steps.iter().all(|s| s.variable.as_deref() == Some("x")));
+}
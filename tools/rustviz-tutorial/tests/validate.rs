@@ -0,0 +1,151 @@
+//! Runs `rustviz-tutorial check` on the book, and checks `validate` finds
+//! each kind of problem in a scratch book.
+
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use rustviz_tutorial::validate::validate;
+use rustviz_tutorial::{Layout, Manifest};
+use tempfile::TempDir;
+
+#[test]
+fn check_passes_on_the_book() {
+    let output = Command::new(env!("CARGO_BIN_EXE_rustviz-tutorial"))
+        .arg("check")
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "\n{stdout}");
+    assert!(stdout.ends_with(" examples ok\n"), "{stdout}");
+}
+
+const MANIFEST: &str = r#"
+[[example]]
+name = "move_assignment"
+chapter = "ownership.md"
+purpose = "Assignment moves ownership."
+
+[[example]]
+name = "twice"
+purpose = "Listed twice."
+visualization = false
+
+[[example]]
+name = "twice"
+purpose = "Listed twice."
+visualization = false
+
+[[example]]
+name = "nowhere"
+purpose = "Has no directory."
+visualization = false
+
+[[example]]
+name = "empty"
+purpose = "Has no source.rs."
+visualization = false
+
+[[example]]
+name = "elsewhere"
+chapter = "structs.md"
+purpose = "Shown in another chapter than the one listed."
+visualization = false
+
+[[example]]
+name = "unassigned"
+purpose = "Shown, but listed without a chapter."
+visualization = false
+
+[[example]]
+name = "lost"
+chapter = "gone.md"
+purpose = "Listed in a chapter that does not exist."
+visualization = false
+"#;
+
+/// A book with one problem of every kind `validate` reports.
+fn scratch_book() -> TempDir {
+    let repo = Layout::discover(Path::new(env!("CARGO_MANIFEST_DIR"))).unwrap();
+    let book = TempDir::new().unwrap();
+    let layout = Layout::new(book.path());
+    fs::write(book.path().join("book.toml"), "").unwrap();
+    fs::write(layout.manifest(), MANIFEST).unwrap();
+
+    let example = layout.example_dir("move_assignment");
+    fs::create_dir_all(&example).unwrap();
+    for entry in fs::read_dir(repo.example_dir("move_assignment")).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, example.join(path.file_name().unwrap())).unwrap();
+    }
+    fs::write(
+        example.join("overlay.toml"),
+        "[[tooltip]]\nline = 9\nvariable = \"x\"\nappend = \"never applies\"\n",
+    )
+    .unwrap();
+    for name in ["twice", "elsewhere", "unassigned", "lost", "stray"] {
+        fs::create_dir_all(layout.example_dir(name)).unwrap();
+        fs::write(layout.example_dir(name).join("source.rs"), "fn main() {}\n").unwrap();
+    }
+    fs::create_dir_all(layout.example_dir("empty")).unwrap();
+    fs::create_dir_all(layout.modified_dir("twice")).unwrap();
+
+    fs::write(
+        layout.src().join("ownership.md"),
+        "{{#rustviz move_assignment}}\n\
+         ![](assets/code_examples/elsewhere/vis_code.svg)\n\
+         ![](assets/code_examples/unassigned/vis_code.svg)\n\
+         ![](assets/code_examples/unknown/vis_code.svg)\n",
+    )
+    .unwrap();
+    fs::write(layout.src().join("structs.md"), "# Structs\n").unwrap();
+    book
+}
+
+#[test]
+fn every_kind_of_problem_is_found() {
+    let book = scratch_book();
+    let layout = Layout::new(book.path());
+    let manifest = Manifest::load(&layout.manifest()).unwrap();
+    let problems: Vec<String> = validate(&layout, &manifest)
+        .unwrap()
+        .iter()
+        .map(|problem| problem.to_string())
+        .collect();
+
+    let root = book.path().display();
+    let examples = format!("{root}/src/assets/code_examples");
+    assert_eq!(
+        problems,
+        [
+            "twice is listed more than once in examples.toml".to_owned(),
+            format!("{examples}/stray is not listed in examples.toml"),
+            format!("{root}/src/assets/modified_examples/twice is not listed in examples.toml"),
+            format!(
+                "{examples}/move_assignment/overlay.toml: \
+                 tooltip for the event of `x` on line 9 matches nothing"
+            ),
+            format!("nowhere: {examples}/nowhere does not exist"),
+            format!("empty: {examples}/empty/source.rs is missing"),
+            "ownership.md refers to elsewhere, but examples.toml assigns it to structs.md"
+                .to_owned(),
+            "ownership.md refers to unassigned, but examples.toml gives it no chapter".to_owned(),
+            "ownership.md refers to unknown, which is not an example".to_owned(),
+            "elsewhere: structs.md does not refer to it".to_owned(),
+            "lost: chapter gone.md does not exist".to_owned(),
+        ]
+    );
+
+    fs::remove_file(
+        layout
+            .example_dir("move_assignment")
+            .join("vis_timeline_print.svg"),
+    )
+    .unwrap();
+    let problems = validate(&layout, &manifest).unwrap();
+    assert!(problems.iter().any(|problem| problem.to_string()
+        == format!(
+            "move_assignment: {examples}/move_assignment/vis_timeline_print.svg is missing"
+        )));
+}